@@ -0,0 +1,394 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::notice::notify_alarm;
+use crate::plc::{unix_millis, PLCError};
+use crate::tags;
+
+type Result<T> = std::result::Result<T, PLCError>;
+
+// 报警历史最多保留的条数
+const MAX_ALARM_HISTORY: usize = 1000;
+
+// 以名称输出，定义时可传入名称或编号
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", try_from = "KindInput")]
+pub enum AlarmKind {
+    HighHigh = 1,
+    High = 2,
+    Low = 3,
+    LowLow = 4,
+    // 布尔量等于 limit 对应的状态时报警
+    State = 5,
+}
+
+impl From<u8> for AlarmKind {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => AlarmKind::HighHigh,
+            3 => AlarmKind::Low,
+            4 => AlarmKind::LowLow,
+            5 => AlarmKind::State,
+            _ => AlarmKind::High, // 默认为高报警
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KindInput {
+    Code(u8),
+    Name(String),
+}
+
+impl TryFrom<KindInput> for AlarmKind {
+    type Error = String;
+
+    fn try_from(input: KindInput) -> std::result::Result<Self, Self::Error> {
+        match input {
+            KindInput::Code(code) => Ok(AlarmKind::from(code)),
+            KindInput::Name(name) => match name.as_str() {
+                "highHigh" => Ok(AlarmKind::HighHigh),
+                "high" => Ok(AlarmKind::High),
+                "low" => Ok(AlarmKind::Low),
+                "lowLow" => Ok(AlarmKind::LowLow),
+                "state" => Ok(AlarmKind::State),
+                _ => Err(format!("未知的报警类型: {}", name)),
+            },
+        }
+    }
+}
+
+/// 报警状态：未确认的报警在恢复后仍保持 Cleared，确认后才回到 Normal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AlarmState {
+    #[default]
+    Normal,
+    Active,
+    Acknowledged,
+    Cleared,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlarmDefinition {
+    pub id: String,
+    pub tag: String,
+    pub kind: AlarmKind,
+    pub limit: f64,
+    // 限值报警恢复时需要回到限值以内的距离，避免在限值附近反复报警
+    #[serde(default)]
+    pub deadband: f64,
+    // 条件持续满足该时长后才报警
+    #[serde(default)]
+    pub on_delay_ms: u64,
+    #[serde(default)]
+    pub message: String,
+}
+
+/// 报警状态变化事件，同时记入报警历史
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AlarmEvent {
+    pub id: String,
+    pub tag: String,
+    pub kind: AlarmKind,
+    pub state: AlarmState,
+    pub value: Option<f64>,
+    pub message: String,
+    pub timestamp: u64,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AlarmStatus {
+    #[serde(flatten)]
+    pub definition: AlarmDefinition,
+    pub state: AlarmState,
+    pub value: Option<f64>,
+    // 进入当前状态的时间
+    pub since: Option<u64>,
+}
+
+struct Alarm {
+    definition: AlarmDefinition,
+    state: AlarmState,
+    value: Option<f64>,
+    since: Option<u64>,
+    // 条件开始满足的时间，用于延时报警
+    pending: Option<Instant>,
+}
+
+impl Alarm {
+    // 报警条件是否成立，已报警时按死区判断是否恢复
+    fn condition(&self, value: f64) -> bool {
+        let limit = self.definition.limit;
+        let deadband = self.definition.deadband.abs();
+        let alarming = matches!(self.state, AlarmState::Active | AlarmState::Acknowledged);
+        match self.definition.kind {
+            AlarmKind::HighHigh | AlarmKind::High if alarming => value > limit - deadband,
+            AlarmKind::HighHigh | AlarmKind::High => value > limit,
+            AlarmKind::Low | AlarmKind::LowLow if alarming => value < limit + deadband,
+            AlarmKind::Low | AlarmKind::LowLow => value < limit,
+            AlarmKind::State => (value != 0.0) == (limit != 0.0),
+        }
+    }
+
+    fn transition(&mut self, state: AlarmState, timestamp: u64) -> AlarmEvent {
+        self.state = state;
+        self.since = Some(timestamp);
+        AlarmEvent {
+            id: self.definition.id.clone(),
+            tag: self.definition.tag.clone(),
+            kind: self.definition.kind,
+            state,
+            value: self.value,
+            message: self.definition.message.clone(),
+            timestamp,
+        }
+    }
+
+    // 根据条件和延时推进状态，状态变化时返回事件
+    fn update(&mut self, condition: bool, now: Instant, timestamp: u64) -> Option<AlarmEvent> {
+        let alarming = matches!(self.state, AlarmState::Active | AlarmState::Acknowledged);
+        if !condition {
+            self.pending = None;
+            return match self.state {
+                AlarmState::Active => Some(self.transition(AlarmState::Cleared, timestamp)),
+                AlarmState::Acknowledged => Some(self.transition(AlarmState::Normal, timestamp)),
+                _ => None,
+            };
+        }
+        if alarming {
+            return None;
+        }
+
+        let pending = *self.pending.get_or_insert(now);
+        if now.duration_since(pending) >= Duration::from_millis(self.definition.on_delay_ms) {
+            self.pending = None;
+            return Some(self.transition(AlarmState::Active, timestamp));
+        }
+        None
+    }
+}
+
+#[derive(Default)]
+struct AlarmTable {
+    alarms: HashMap<String, Alarm>,
+    history: VecDeque<AlarmEvent>,
+}
+
+impl AlarmTable {
+    fn record(&mut self, event: &AlarmEvent) {
+        self.history.push_back(event.clone());
+        while self.history.len() > MAX_ALARM_HISTORY {
+            self.history.pop_front();
+        }
+    }
+}
+
+lazy_static! {
+    static ref ALARMS: Mutex<AlarmTable> = Mutex::new(AlarmTable::default());
+}
+
+// 推送在释放报警表的锁之后进行
+fn emit(events: Vec<AlarmEvent>) {
+    for event in events {
+        notify_alarm(event);
+    }
+}
+
+/// 新增或替换报警，替换时重置其状态
+pub async fn define_alarm(definition: AlarmDefinition) -> Result<()> {
+    if definition.id.trim().is_empty() {
+        return Err(PLCError::Other("报警ID不能为空".to_string()));
+    }
+    if !tags::tag_exists(&definition.tag).await {
+        return Err(PLCError::TagNotFound(definition.tag));
+    }
+
+    // 用标签的当前值立即评估，数值不变化时也能进入报警
+    let current = tags::latest_values(std::slice::from_ref(&definition.tag))
+        .await
+        .remove(&definition.tag)
+        .flatten();
+    let id = definition.id.clone();
+    let alarm = Alarm {
+        definition,
+        state: AlarmState::Normal,
+        value: None,
+        since: None,
+        pending: None,
+    };
+    ALARMS.lock().await.alarms.insert(id.clone(), alarm);
+
+    if let Some(value) = current {
+        evaluate_matching(value, |alarm| alarm.definition.id == id).await;
+    }
+    Ok(())
+}
+
+pub async fn remove_alarm(id: &str) -> Result<()> {
+    ALARMS
+        .lock()
+        .await
+        .alarms
+        .remove(id)
+        .map(|_| ())
+        .ok_or_else(|| PLCError::Other(format!("报警未找到: {}", id)))
+}
+
+pub async fn list_alarms() -> Vec<AlarmStatus> {
+    let table = ALARMS.lock().await;
+    let mut alarms: Vec<AlarmStatus> = table
+        .alarms
+        .values()
+        .map(|alarm| AlarmStatus {
+            definition: alarm.definition.clone(),
+            state: alarm.state,
+            value: alarm.value,
+            since: alarm.since,
+        })
+        .collect();
+    alarms.sort_by(|a, b| a.definition.id.cmp(&b.definition.id));
+    alarms
+}
+
+/// 最近的报警历史，按时间先后排列
+pub async fn history(limit: usize) -> Vec<AlarmEvent> {
+    let table = ALARMS.lock().await;
+    let skip = table.history.len().saturating_sub(limit);
+    table.history.iter().skip(skip).cloned().collect()
+}
+
+// 确认报警：报警中转为已确认，已恢复的回到正常
+fn acknowledge_alarm(alarm: &mut Alarm, timestamp: u64) -> Option<AlarmEvent> {
+    match alarm.state {
+        AlarmState::Active => Some(alarm.transition(AlarmState::Acknowledged, timestamp)),
+        AlarmState::Cleared => Some(alarm.transition(AlarmState::Normal, timestamp)),
+        _ => None,
+    }
+}
+
+/// 确认报警，id 为空时确认全部
+pub async fn acknowledge(id: Option<&str>) -> Result<usize> {
+    let timestamp = unix_millis();
+    let events = {
+        let mut table = ALARMS.lock().await;
+        let events: Vec<AlarmEvent> = match id {
+            Some(id) => {
+                let alarm = table
+                    .alarms
+                    .get_mut(id)
+                    .ok_or_else(|| PLCError::Other(format!("报警未找到: {}", id)))?;
+                acknowledge_alarm(alarm, timestamp).into_iter().collect()
+            }
+            None => table
+                .alarms
+                .values_mut()
+                .filter_map(|alarm| acknowledge_alarm(alarm, timestamp))
+                .collect(),
+        };
+        for event in &events {
+            table.record(event);
+        }
+        events
+    };
+
+    let count = events.len();
+    emit(events);
+    Ok(count)
+}
+
+// 条件开始满足的延时报警各自启动定时器，到时再检查一次
+fn schedule_delays(delays: Vec<(String, u64)>) {
+    for (id, on_delay_ms) in delays {
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(on_delay_ms)).await;
+            check_delay(&id).await;
+        });
+    }
+}
+
+/// 用标签的新值评估其报警，读取失败时不改变报警状态
+pub async fn evaluate(tag: &str, value: f64) {
+    evaluate_matching(value, |alarm| alarm.definition.tag == tag).await;
+}
+
+async fn evaluate_matching(value: f64, matches: impl Fn(&Alarm) -> bool) {
+    let now = Instant::now();
+    let timestamp = unix_millis();
+    let (events, delays) = {
+        let mut table = ALARMS.lock().await;
+        let mut events = Vec::new();
+        let mut delays = Vec::new();
+        for alarm in table.alarms.values_mut().filter(|alarm| matches(alarm)) {
+            alarm.value = Some(value);
+            let was_pending = alarm.pending.is_some();
+            let condition = alarm.condition(value);
+            events.extend(alarm.update(condition, now, timestamp));
+            if !was_pending && alarm.pending.is_some() {
+                delays.push((alarm.definition.id.clone(), alarm.definition.on_delay_ms));
+            }
+        }
+        for event in &events {
+            table.record(event);
+        }
+        (events, delays)
+    };
+    schedule_delays(delays);
+    emit(events);
+}
+
+// 延时到期后检查报警，数值不变化时也能按时进入报警；期间条件恢复过的由新的定时器处理
+async fn check_delay(id: &str) {
+    let now = Instant::now();
+    let timestamp = unix_millis();
+    let event = {
+        let mut table = ALARMS.lock().await;
+        let Some(alarm) = table.alarms.get_mut(id) else {
+            return;
+        };
+        if alarm.pending.is_none() {
+            return;
+        }
+        let condition = alarm.value.is_some_and(|value| alarm.condition(value));
+        let event = alarm.update(condition, now, timestamp);
+        if let Some(event) = &event {
+            table.record(event);
+        }
+        event
+    };
+    emit(event.into_iter().collect());
+}
+
+/// 删除标签时一并删除其报警，未恢复的报警先回到正常状态
+pub async fn remove_for_tag(tag: &str) {
+    let timestamp = unix_millis();
+    let events = {
+        let mut table = ALARMS.lock().await;
+        let ids: Vec<String> = table
+            .alarms
+            .values()
+            .filter(|alarm| alarm.definition.tag == tag)
+            .map(|alarm| alarm.definition.id.clone())
+            .collect();
+        let mut events = Vec::new();
+        for id in ids {
+            if let Some(mut alarm) = table.alarms.remove(&id) {
+                if alarm.state != AlarmState::Normal {
+                    events.push(alarm.transition(AlarmState::Normal, timestamp));
+                }
+            }
+        }
+        for event in &events {
+            table.record(event);
+        }
+        events
+    };
+    emit(events);
+}
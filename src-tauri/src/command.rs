@@ -1,6 +1,20 @@
+use crate::alarm::{self, AlarmDefinition, AlarmEvent, AlarmStatus};
+use crate::guard::{self, WriteError, WriteLimit};
+use crate::historian::{self, HistoryPoint};
+use crate::logger::{self, LoggerConfig, LoggerStatus};
 use crate::modbus::get_all_serial_ports;
 use crate::modbus::MODBUS_MANAGER;
-use crate::plc::TASK_SCHEDULER;
+use crate::plc::{
+    AddressRef, DataType, Deadband, DisconnectPolicy, RegisterTable, TaskStatus, TriggerEdge,
+    WriteVerify, TASK_SCHEDULER,
+};
+use crate::project::{self, LoadReport};
+use crate::register_map::{self, ImportReport};
+use crate::script::{self, ScriptInfo};
+use crate::stats::SchedulerStats;
+use crate::tags::{self, RecentValue, TagDefinition, VirtualTagDefinition};
+use crate::writer::{ResolvedItem, WriteItem, WriteResult};
+use std::path::Path;
 
 #[tauri::command]
 pub async fn get_serial_ports() -> Result<Vec<String>, String> {
@@ -18,6 +32,10 @@ pub async fn modbus_create_tcp_connection(ip: String, port: u16) -> Result<Strin
         .create_tcp_connection(&ip, port)
         .await
         .map_err(|e| e.to_string())?;
+    TASK_SCHEDULER
+        .restore_client_tasks(id)
+        .await
+        .map_err(|e| e.to_string())?;
     Ok(id.to_string())
 }
 
@@ -36,6 +54,10 @@ pub async fn modbus_create_serial_connection(
         .create_serial_connection(&serial_port, baud_rate, slave_id)
         .await
         .map_err(|e| e.to_string())?;
+    TASK_SCHEDULER
+        .restore_client_tasks(id)
+        .await
+        .map_err(|e| e.to_string())?;
     Ok(id.to_string())
 }
 
@@ -44,10 +66,13 @@ pub async fn modbus_disconnect(client_id: String) -> Result<(), String> {
     #[cfg(debug_assertions)]
     println!("断开连接 - Client ID: {}", client_id);
     let client_id = to_i64(&client_id)?;
-    MODBUS_MANAGER
-        .disconnect(client_id)
+    let result = MODBUS_MANAGER.disconnect(client_id).await;
+    // 无论断开是否成功，该连接都已不可用，按策略清理其任务
+    TASK_SCHEDULER
+        .release_client_tasks(client_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    result.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -58,6 +83,37 @@ pub async fn modbus_connection_exists(client_id: String) -> Result<bool, String>
     Ok(MODBUS_MANAGER.connection_exists(client_id).await)
 }
 
+#[tauri::command]
+pub async fn modbus_set_queue_depth(depth: usize) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!("设置请求队列深度 - Depth: {}", depth);
+    MODBUS_MANAGER
+        .set_max_queue_depth(depth)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn modbus_set_request_timeout(timeout_ms: String) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!("设置请求超时 - Timeout: {}ms", timeout_ms);
+    let timeout_ms = to_u64(&timeout_ms)?;
+    MODBUS_MANAGER
+        .set_request_timeout(timeout_ms)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn modbus_set_disconnect_policy(policy: u8) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!("设置断开连接策略 - Policy: {}", policy);
+    TASK_SCHEDULER
+        .set_disconnect_policy(DisconnectPolicy::from(policy))
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn plc_start() -> Result<(), String> {
     #[cfg(debug_assertions)]
@@ -76,7 +132,7 @@ pub async fn plc_stop() -> Result<(), String> {
 pub async fn plc_register_task(
     client_id: String,
     interval_ms: String,
-    address: u16,
+    address: AddressRef,
     data_type: u8,
     read_only: bool,
 ) -> Result<(), String> {
@@ -86,6 +142,9 @@ pub async fn plc_register_task(
         client_id, interval_ms, address, data_type, read_only
     );
     let client_id = to_i64(&client_id)?;
+    let (address, read_only) = address
+        .resolve(DataType::from(data_type), read_only)
+        .map_err(|e| e.to_string())?;
     let interval_ms = to_u64(&interval_ms)?;
     TASK_SCHEDULER
         .register_task(client_id, interval_ms, address, data_type, read_only)
@@ -96,7 +155,7 @@ pub async fn plc_register_task(
 #[tauri::command]
 pub async fn plc_unregister_task(
     client_id: String,
-    address: u16,
+    address: AddressRef,
     data_type: u8,
     read_only: bool,
 ) -> Result<(), String> {
@@ -106,6 +165,9 @@ pub async fn plc_unregister_task(
         client_id, address
     );
     let client_id = to_i64(&client_id)?;
+    let (address, read_only) = address
+        .resolve(DataType::from(data_type), read_only)
+        .map_err(|e| e.to_string())?;
     TASK_SCHEDULER
         .unregister_task(client_id, address, data_type, read_only)
         .await
@@ -113,13 +175,369 @@ pub async fn plc_unregister_task(
 }
 
 #[tauri::command]
-pub async fn plc_read_bool(client_id: String, address: u16) -> Result<bool, String> {
+pub async fn plc_set_task_interval(
+    client_id: String,
+    address: AddressRef,
+    data_type: u8,
+    read_only: bool,
+    interval_ms: String,
+) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "修改任务周期 - Client ID: {}, Address: {}, Interval: {}ms",
+        client_id, address, interval_ms
+    );
+    let client_id = to_i64(&client_id)?;
+    let (address, read_only) = address
+        .resolve(DataType::from(data_type), read_only)
+        .map_err(|e| e.to_string())?;
+    let interval_ms = to_u64(&interval_ms)?;
+    TASK_SCHEDULER
+        .set_task_interval(client_id, address, data_type, read_only, interval_ms)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_pause_task(
+    client_id: String,
+    address: AddressRef,
+    data_type: u8,
+    read_only: bool,
+) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!("暂停任务 - Client ID: {}, Address: {}", client_id, address);
+    let client_id = to_i64(&client_id)?;
+    let (address, read_only) = address
+        .resolve(DataType::from(data_type), read_only)
+        .map_err(|e| e.to_string())?;
+    TASK_SCHEDULER
+        .set_task_paused(client_id, address, data_type, read_only, true)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_resume_task(
+    client_id: String,
+    address: AddressRef,
+    data_type: u8,
+    read_only: bool,
+) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!("恢复任务 - Client ID: {}, Address: {}", client_id, address);
+    let client_id = to_i64(&client_id)?;
+    let (address, read_only) = address
+        .resolve(DataType::from(data_type), read_only)
+        .map_err(|e| e.to_string())?;
+    TASK_SCHEDULER
+        .set_task_paused(client_id, address, data_type, read_only, false)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_pause_client_tasks(client_id: String) -> Result<usize, String> {
+    #[cfg(debug_assertions)]
+    println!("暂停连接的所有任务 - Client ID: {}", client_id);
+    let client_id = to_i64(&client_id)?;
+    TASK_SCHEDULER
+        .set_client_paused(client_id, true)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_resume_client_tasks(client_id: String) -> Result<usize, String> {
+    #[cfg(debug_assertions)]
+    println!("恢复连接的所有任务 - Client ID: {}", client_id);
+    let client_id = to_i64(&client_id)?;
+    TASK_SCHEDULER
+        .set_client_paused(client_id, false)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_schedule_read(
+    client_id: String,
+    address: AddressRef,
+    data_type: u8,
+    read_only: bool,
+    quantity: u16,
+    delay_ms: String,
+) -> Result<String, String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "注册延时读取 - Client ID: {}, Address: {}, Quantity: {}, Delay: {}ms",
+        client_id, address, quantity, delay_ms
+    );
+    let client_id = to_i64(&client_id)?;
+    let (address, read_only) = address
+        .resolve(DataType::from(data_type), read_only)
+        .map_err(|e| e.to_string())?;
+    let delay_ms = to_u64(&delay_ms)?;
+    let id = TASK_SCHEDULER
+        .schedule_read(client_id, address, data_type, read_only, quantity, delay_ms)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(id.to_string())
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn plc_register_trigger(
+    client_id: String,
+    address: AddressRef,
+    data_type: u8,
+    read_only: bool,
+    quantity: u16,
+    trigger_client_id: String,
+    trigger_address: AddressRef,
+    trigger_data_type: u8,
+    trigger_read_only: bool,
+    edge: u8,
+) -> Result<String, String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "注册触发读取 - Client ID: {}, Address: {}, Quantity: {}, Trigger: {}/{}, Edge: {}",
+        client_id, address, quantity, trigger_client_id, trigger_address, edge
+    );
+    let client_id = to_i64(&client_id)?;
+    let (address, read_only) = address
+        .resolve(DataType::from(data_type), read_only)
+        .map_err(|e| e.to_string())?;
+    let trigger_client_id = to_i64(&trigger_client_id)?;
+    let (trigger_address, trigger_read_only) = trigger_address
+        .resolve(DataType::from(trigger_data_type), trigger_read_only)
+        .map_err(|e| e.to_string())?;
+    let id = TASK_SCHEDULER
+        .register_trigger(
+            client_id,
+            address,
+            data_type,
+            read_only,
+            quantity,
+            trigger_client_id,
+            trigger_address,
+            trigger_data_type,
+            trigger_read_only,
+            TriggerEdge::from(edge),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(id.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_unregister_trigger(id: String) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!("注销触发读取 - ID: {}", id);
+    let id = id.parse().map_err(|e| format!("无效的触发读取ID: {}", e))?;
+    TASK_SCHEDULER
+        .unregister_trigger(id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_set_backoff(
+    failure_threshold: u32,
+    probe_interval_ms: String,
+) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "设置故障退避 - Threshold: {}, Probe Interval: {}ms",
+        failure_threshold, probe_interval_ms
+    );
+    let probe_interval_ms = to_u64(&probe_interval_ms)?;
+    TASK_SCHEDULER
+        .set_backoff(failure_threshold, probe_interval_ms)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_set_read_optimizer(enabled: bool, max_gap: u16) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "设置块读取优化 - Enabled: {}, Max Gap: {}",
+        enabled, max_gap
+    );
+    TASK_SCHEDULER
+        .set_read_optimizer(enabled, max_gap)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_set_task_deadband(
+    client_id: String,
+    address: AddressRef,
+    data_type: u8,
+    read_only: bool,
+    deadband_type: u8,
+    deadband: f64,
+) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "设置任务死区 - Client ID: {}, Address: {}, Type: {}, Deadband: {}",
+        client_id, address, deadband_type, deadband
+    );
+    let client_id = to_i64(&client_id)?;
+    let (address, read_only) = address
+        .resolve(DataType::from(data_type), read_only)
+        .map_err(|e| e.to_string())?;
+    TASK_SCHEDULER
+        .set_task_deadband(
+            client_id,
+            address,
+            data_type,
+            read_only,
+            Deadband::from_type(deadband_type, deadband),
+        )
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_set_integrity_interval(interval_ms: String) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!("设置完整性上报周期 - Interval: {}ms", interval_ms);
+    let interval_ms = to_u64(&interval_ms)?;
+    TASK_SCHEDULER
+        .set_integrity_interval(interval_ms)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_get_task_status(
+    client_id: String,
+    address: AddressRef,
+    data_type: u8,
+    read_only: bool,
+) -> Result<TaskStatus, String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "获取任务状态 - Client ID: {}, Address: {}",
+        client_id, address
+    );
+    let client_id = to_i64(&client_id)?;
+    let (address, read_only) = address
+        .resolve(DataType::from(data_type), read_only)
+        .map_err(|e| e.to_string())?;
+    TASK_SCHEDULER
+        .get_task_status(client_id, address, data_type, read_only)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_get_scheduler_stats() -> Result<SchedulerStats, String> {
+    #[cfg(debug_assertions)]
+    println!("获取调度器统计");
+    Ok(TASK_SCHEDULER.get_stats().await)
+}
+
+#[tauri::command]
+pub async fn plc_reset_scheduler_stats() -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!("重置调度器统计");
+    TASK_SCHEDULER
+        .reset_stats()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_set_stats_interval(interval_ms: String) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!("设置统计推送周期 - Interval: {}ms", interval_ms);
+    let interval_ms = to_u64(&interval_ms)?;
+    TASK_SCHEDULER
+        .set_stats_interval(interval_ms)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_set_batch_mode(enabled: bool, window_ms: String) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "设置批量推送 - Enabled: {}, Window: {}ms",
+        enabled, window_ms
+    );
+    let window_ms = to_u64(&window_ms)?;
+    crate::notice::set_batch_mode(enabled, window_ms);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn plc_subscribe(
+    window: tauri::Window,
+    client_id: String,
+    data_type: u8,
+    read_only: bool,
+    addresses: Option<Vec<AddressRef>>,
+) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "订阅数据 - Window: {}, Client ID: {}, Data Type: {}, Read Only: {}, Addresses: {:?}",
+        window.label(),
+        client_id,
+        data_type,
+        read_only,
+        addresses
+    );
+    let client_id = to_i64(&client_id)?;
+    let (table, addresses) = resolve_points(data_type, read_only, addresses)?;
+    crate::notice::subscribe(window.label(), client_id, table, addresses);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn plc_unsubscribe(
+    window: tauri::Window,
+    client_id: String,
+    data_type: u8,
+    read_only: bool,
+    addresses: Option<Vec<AddressRef>>,
+) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "取消订阅 - Window: {}, Client ID: {}, Data Type: {}, Read Only: {}, Addresses: {:?}",
+        window.label(),
+        client_id,
+        data_type,
+        read_only,
+        addresses
+    );
+    let client_id = to_i64(&client_id)?;
+    let (table, addresses) = resolve_points(data_type, read_only, addresses)?;
+    crate::notice::unsubscribe(window.label(), client_id, table, addresses);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn plc_clear_subscriptions(window: tauri::Window) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!("清除订阅 - Window: {}", window.label());
+    crate::notice::remove_subscriber(window.label());
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn plc_read_bool(client_id: String, address: AddressRef) -> Result<bool, String> {
     #[cfg(debug_assertions)]
     println!(
         "读取布尔值 - Client ID: {}, Address: {}",
         client_id, address
     );
     let client_id = to_i64(&client_id)?;
+    let (address, _) = address
+        .resolve(DataType::Bool, false)
+        .map_err(|e| e.to_string())?;
     crate::plc::read_bool(client_id, address)
         .await
         .map_err(|e| e.to_string())
@@ -128,7 +546,7 @@ pub async fn plc_read_bool(client_id: String, address: u16) -> Result<bool, Stri
 #[tauri::command]
 pub async fn plc_read_word(
     client_id: String,
-    address: u16,
+    address: AddressRef,
     read_only: bool,
 ) -> Result<u16, String> {
     #[cfg(debug_assertions)]
@@ -137,6 +555,9 @@ pub async fn plc_read_word(
         client_id, address, read_only
     );
     let client_id = to_i64(&client_id)?;
+    let (address, read_only) = address
+        .resolve(DataType::Word, read_only)
+        .map_err(|e| e.to_string())?;
     crate::plc::read_word(client_id, address, read_only)
         .await
         .map_err(|e| e.to_string())
@@ -145,7 +566,7 @@ pub async fn plc_read_word(
 #[tauri::command]
 pub async fn plc_read_dword(
     client_id: String,
-    address: u16,
+    address: AddressRef,
     read_only: bool,
 ) -> Result<u32, String> {
     #[cfg(debug_assertions)]
@@ -154,6 +575,9 @@ pub async fn plc_read_dword(
         client_id, address, read_only
     );
     let client_id = to_i64(&client_id)?;
+    let (address, read_only) = address
+        .resolve(DataType::Dword, read_only)
+        .map_err(|e| e.to_string())?;
     crate::plc::read_dword(client_id, address, read_only)
         .await
         .map_err(|e| e.to_string())
@@ -162,7 +586,7 @@ pub async fn plc_read_dword(
 #[tauri::command]
 pub async fn plc_read_float(
     client_id: String,
-    address: u16,
+    address: AddressRef,
     read_only: bool,
 ) -> Result<f32, String> {
     #[cfg(debug_assertions)]
@@ -171,63 +595,491 @@ pub async fn plc_read_float(
         client_id, address, read_only
     );
     let client_id = to_i64(&client_id)?;
+    let (address, read_only) = address
+        .resolve(DataType::Float, read_only)
+        .map_err(|e| e.to_string())?;
     crate::plc::read_float(client_id, address, read_only)
         .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn plc_write_bool(client_id: String, address: u16, value: bool) -> Result<(), String> {
+pub async fn plc_write_bool(
+    client_id: String,
+    address: AddressRef,
+    value: bool,
+    verify: Option<WriteVerify>,
+) -> Result<(), WriteError> {
     #[cfg(debug_assertions)]
     println!(
-        "写入布尔值 - Client ID: {}, Address: {}, Value: {}",
-        client_id, address, value
+        "写入布尔值 - Client ID: {}, Address: {}, Value: {}, Verify: {:?}",
+        client_id, address, value, verify
     );
     let client_id = to_i64(&client_id)?;
-    crate::plc::write_bool(client_id, address, value)
+    let address = address
+        .resolve_writable(DataType::Bool)
+        .map_err(|e| e.to_string())?;
+    guard::check(client_id, address, DataType::Bool, value as u8 as f64).await?;
+    crate::plc::write_bool(client_id, address, value, verify).await?;
+    guard::commit(client_id, address, DataType::Bool).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn plc_write_word(
+    client_id: String,
+    address: AddressRef,
+    value: u16,
+    verify: Option<WriteVerify>,
+) -> Result<(), WriteError> {
+    #[cfg(debug_assertions)]
+    println!(
+        "写入字 - Client ID: {}, Address: {}, Value: {}, Verify: {:?}",
+        client_id, address, value, verify
+    );
+    let client_id = to_i64(&client_id)?;
+    let address = address
+        .resolve_writable(DataType::Word)
+        .map_err(|e| e.to_string())?;
+    guard::check(client_id, address, DataType::Word, value as f64).await?;
+    crate::plc::write_word(client_id, address, value, verify).await?;
+    guard::commit(client_id, address, DataType::Word).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn plc_write_dword(
+    client_id: String,
+    address: AddressRef,
+    value: u32,
+    verify: Option<WriteVerify>,
+) -> Result<(), WriteError> {
+    #[cfg(debug_assertions)]
+    println!(
+        "写入双字 - Client ID: {}, Address: {}, Value: {}, Verify: {:?}",
+        client_id, address, value, verify
+    );
+    let client_id = to_i64(&client_id)?;
+    let address = address
+        .resolve_writable(DataType::Dword)
+        .map_err(|e| e.to_string())?;
+    guard::check(client_id, address, DataType::Dword, value as f64).await?;
+    crate::plc::write_dword(client_id, address, value, verify).await?;
+    guard::commit(client_id, address, DataType::Dword).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn plc_write_float(
+    client_id: String,
+    address: AddressRef,
+    value: f32,
+    verify: Option<WriteVerify>,
+) -> Result<(), WriteError> {
+    #[cfg(debug_assertions)]
+    println!(
+        "写入浮点数 - Client ID: {}, Address: {}, Value: {}, Verify: {:?}",
+        client_id, address, value, verify
+    );
+    let client_id = to_i64(&client_id)?;
+    let address = address
+        .resolve_writable(DataType::Float)
+        .map_err(|e| e.to_string())?;
+    guard::check(client_id, address, DataType::Float, value as f64).await?;
+    crate::plc::write_float(client_id, address, value, verify).await?;
+    guard::commit(client_id, address, DataType::Float).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn plc_write_batch(
+    client_id: String,
+    items: Vec<WriteItem>,
+) -> Result<Vec<WriteResult>, WriteError> {
+    #[cfg(debug_assertions)]
+    println!(
+        "批量写入 - Client ID: {}, Items: {}",
+        client_id,
+        items.len()
+    );
+    let client_id = to_i64(&client_id)?;
+    let items = items
+        .iter()
+        .map(WriteItem::resolve)
+        .collect::<Result<Vec<ResolvedItem>, _>>()
+        .map_err(|e| e.to_string())?;
+    // 任一写入项被拒绝时整批不执行
+    for item in &items {
+        if let Err(rejection) =
+            guard::check(client_id, item.address, item.data_type, item.value.as_f64()).await
+        {
+            return Err(WriteError::Rejected {
+                message: format!("地址 {} 写入被拒绝: {}", item.address, rejection),
+                rejection,
+            });
+        }
+    }
+    let results = crate::writer::write_batch(client_id, &items)
+        .await
+        .map_err(|e| e.to_string())?;
+    // 只有实际写入成功的项才计入写入间隔
+    for result in results.iter().filter(|result| result.success) {
+        let item = &items[result.index];
+        guard::commit(client_id, item.address, item.data_type).await;
+    }
+    Ok(results)
+}
+
+#[tauri::command]
+pub async fn plc_set_write_limit(
+    client_id: String,
+    address: AddressRef,
+    data_type: u8,
+    limit: WriteLimit,
+) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "设置写入限制 - Client ID: {}, Address: {}, Limit: {:?}",
+        client_id, address, limit
+    );
+    let client_id = to_i64(&client_id)?;
+    let data_type = DataType::from(data_type);
+    let address = address
+        .resolve_writable(data_type)
+        .map_err(|e| e.to_string())?;
+    guard::set_limit(client_id, address, data_type, limit).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn plc_clear_write_limit(
+    client_id: String,
+    address: AddressRef,
+    data_type: u8,
+) -> Result<bool, String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "清除写入限制 - Client ID: {}, Address: {}",
+        client_id, address
+    );
+    let client_id = to_i64(&client_id)?;
+    let data_type = DataType::from(data_type);
+    let address = address
+        .resolve_writable(data_type)
+        .map_err(|e| e.to_string())?;
+    Ok(guard::clear_limit(client_id, address, data_type).await)
+}
+
+#[tauri::command]
+pub async fn plc_define_tag(tag: TagDefinition) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "定义标签 - Name: {}, Client ID: {}, Address: {}",
+        tag.name, tag.client_id, tag.address
+    );
+    tags::define_tag(tag).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_remove_tag(name: String) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!("删除标签 - Name: {}", name);
+    tags::remove_tag(&name).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_list_tags() -> Result<Vec<TagDefinition>, String> {
+    Ok(tags::list_tags().await)
+}
+
+#[tauri::command]
+pub async fn plc_define_virtual_tag(tag: VirtualTagDefinition) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "定义虚拟标签 - Name: {}, Expression: {}",
+        tag.name, tag.expression
+    );
+    tags::define_virtual_tag(tag)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_remove_virtual_tag(name: String) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!("删除虚拟标签 - Name: {}", name);
+    tags::remove_virtual_tag(&name)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_list_virtual_tags() -> Result<Vec<VirtualTagDefinition>, String> {
+    Ok(tags::list_virtual_tags().await)
+}
+
+#[tauri::command]
+pub async fn plc_read_tag(name: String) -> Result<f64, String> {
+    #[cfg(debug_assertions)]
+    println!("读取标签 - Name: {}", name);
+    tags::read_tag(&name).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_write_tag(
+    name: String,
+    value: f64,
+    verify: Option<WriteVerify>,
+) -> Result<(), WriteError> {
+    #[cfg(debug_assertions)]
+    println!("写入标签 - Name: {}, Value: {}", name, value);
+    let tag = tags::get_tag(&name).await.map_err(|e| e.to_string())?;
+    let raw = tag.to_raw(value).map_err(|e| e.to_string())?;
+    guard::check(tag.client_id, tag.address, tag.data_type, raw).await?;
+    tags::write_tag(&tag, raw, verify).await?;
+    guard::commit(tag.client_id, tag.address, tag.data_type).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn project_save(path: String) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!("保存项目 - Path: {}", path);
+    project::save(Path::new(&path))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn project_load(path: String) -> Result<LoadReport, String> {
+    #[cfg(debug_assertions)]
+    println!("加载项目 - Path: {}", path);
+    project::load(Path::new(&path))
         .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn plc_write_word(client_id: String, address: u16, value: u16) -> Result<(), String> {
+pub async fn plc_import_csv(
+    path: String,
+    client_id: String,
+    default_interval_ms: String,
+    dry_run: bool,
+) -> Result<ImportReport, String> {
     #[cfg(debug_assertions)]
     println!(
-        "写入字 - Client ID: {}, Address: {}, Value: {}",
-        client_id, address, value
+        "导入寄存器表 - Path: {}, Client ID: {}, Dry Run: {}",
+        path, client_id, dry_run
     );
     let client_id = to_i64(&client_id)?;
-    crate::plc::write_word(client_id, address, value)
+    let default_interval_ms = to_u64(&default_interval_ms)?;
+    register_map::import_csv(Path::new(&path), client_id, default_interval_ms, dry_run)
         .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn plc_write_dword(client_id: String, address: u16, value: u32) -> Result<(), String> {
+pub async fn plc_export_csv(path: String, client_id: String) -> Result<usize, String> {
+    #[cfg(debug_assertions)]
+    println!("导出寄存器表 - Path: {}, Client ID: {}", path, client_id);
+    let client_id = to_i64(&client_id)?;
+    register_map::export_csv(Path::new(&path), client_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn historian_enable(
+    client_id: String,
+    address: AddressRef,
+    data_type: u8,
+    read_only: bool,
+    retention_hours: String,
+) -> Result<(), String> {
     #[cfg(debug_assertions)]
     println!(
-        "写入双字 - Client ID: {}, Address: {}, Value: {}",
-        client_id, address, value
+        "启用历史记录 - Client ID: {}, Address: {}, Retention: {}h",
+        client_id, address, retention_hours
     );
     let client_id = to_i64(&client_id)?;
-    crate::plc::write_dword(client_id, address, value)
+    let data_type = DataType::from(data_type);
+    let (address, read_only) = address
+        .resolve(data_type, read_only)
+        .map_err(|e| e.to_string())?;
+    let retention_ms = to_u64(&retention_hours)?.saturating_mul(3_600_000);
+    historian::enable(client_id, address, data_type, read_only, retention_ms)
         .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn plc_write_float(client_id: String, address: u16, value: f32) -> Result<(), String> {
+pub async fn historian_disable(
+    client_id: String,
+    address: AddressRef,
+    data_type: u8,
+    read_only: bool,
+) -> Result<bool, String> {
     #[cfg(debug_assertions)]
     println!(
-        "写入浮点数 - Client ID: {}, Address: {}, Value: {}",
-        client_id, address, value
+        "停止历史记录 - Client ID: {}, Address: {}",
+        client_id, address
     );
     let client_id = to_i64(&client_id)?;
-    crate::plc::write_float(client_id, address, value)
+    let data_type = DataType::from(data_type);
+    let (address, read_only) = address
+        .resolve(data_type, read_only)
+        .map_err(|e| e.to_string())?;
+    Ok(historian::disable(client_id, address, data_type, read_only).await)
+}
+
+#[tauri::command]
+pub async fn historian_query(
+    client_id: String,
+    address: AddressRef,
+    data_type: u8,
+    read_only: bool,
+    start: String,
+    end: String,
+    bucket_ms: String,
+) -> Result<Vec<HistoryPoint>, String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "查询历史数据 - Client ID: {}, Address: {}, Range: {}-{}, Bucket: {}ms",
+        client_id, address, start, end, bucket_ms
+    );
+    let client_id = to_i64(&client_id)?;
+    let data_type = DataType::from(data_type);
+    let (address, read_only) = address
+        .resolve(data_type, read_only)
+        .map_err(|e| e.to_string())?;
+    let table = RegisterTable::of(data_type, read_only);
+    historian::query(
+        client_id,
+        table,
+        address,
+        to_u64(&start)?,
+        to_u64(&end)?,
+        to_u64(&bucket_ms)?,
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plc_get_recent_values(tag: String, since: String) -> Result<Vec<RecentValue>, String> {
+    #[cfg(debug_assertions)]
+    println!("获取最近数据 - Tag: {}, Since: {}", tag, since);
+    let since = to_u64(&since)?;
+    tags::recent_values(&tag, since)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn logger_start(config: LoggerConfig) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "启动数据记录 - Tags: {:?}, Period: {}ms, Directory: {}",
+        config.tags, config.sample_period_ms, config.directory
+    );
+    logger::start(config).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn logger_stop() -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!("停止数据记录");
+    logger::stop().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn logger_status() -> Result<LoggerStatus, String> {
+    Ok(logger::status().await)
+}
+
+#[tauri::command]
+pub async fn alarm_define(alarm: AlarmDefinition) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!(
+        "定义报警 - Id: {}, Tag: {}, Kind: {:?}, Limit: {}",
+        alarm.id, alarm.tag, alarm.kind, alarm.limit
+    );
+    alarm::define_alarm(alarm).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn alarm_remove(id: String) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!("删除报警 - Id: {}", id);
+    alarm::remove_alarm(&id).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn alarm_list() -> Result<Vec<AlarmStatus>, String> {
+    Ok(alarm::list_alarms().await)
+}
+
+#[tauri::command]
+pub async fn alarm_acknowledge(id: Option<String>) -> Result<usize, String> {
+    #[cfg(debug_assertions)]
+    println!("确认报警 - Id: {:?}", id);
+    alarm::acknowledge(id.as_deref())
         .await
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn alarm_history(limit: usize) -> Result<Vec<AlarmEvent>, String> {
+    Ok(alarm::history(limit).await)
+}
+
+#[tauri::command]
+pub async fn script_run(name: String, source: String) -> Result<String, String> {
+    #[cfg(debug_assertions)]
+    println!("运行脚本 - Name: {}", name);
+    script::run(name, source).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn script_stop(id: String) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    println!("停止脚本 - Id: {}", id);
+    script::stop(&id).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn script_list() -> Result<Vec<ScriptInfo>, String> {
+    Ok(script::list().await)
+}
+
+// 解析订阅的地址，地址引用中的数据区优先于传入的 read_only
+fn resolve_points(
+    data_type: u8,
+    read_only: bool,
+    addresses: Option<Vec<AddressRef>>,
+) -> Result<(RegisterTable, Option<Vec<u16>>), String> {
+    let data_type = DataType::from(data_type);
+    let Some(addresses) = addresses else {
+        return Ok((RegisterTable::of(data_type, read_only), None));
+    };
+
+    let mut table = None;
+    let mut offsets = Vec::with_capacity(addresses.len());
+    for address in addresses {
+        let (offset, read_only) = address
+            .resolve(data_type, read_only)
+            .map_err(|e| e.to_string())?;
+        let resolved = RegisterTable::of(data_type, read_only);
+        if table.is_some_and(|table| table != resolved) {
+            return Err("同一次订阅的地址必须属于同一数据区".to_string());
+        }
+        table = Some(resolved);
+        offsets.push(offset);
+    }
+    let table = table.unwrap_or(RegisterTable::of(data_type, read_only));
+    Ok((table, Some(offsets)))
+}
+
 fn to_i64(client_id: &str) -> Result<i64, String> {
     client_id
         .parse()
@@ -0,0 +1,433 @@
+// 虚拟标签使用的表达式语言，所有值均为 f64，布尔结果用 1 和 0 表示
+// 运算符优先级与 Rust 相同，`status & 0x04 != 0` 先做按位与再比较
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinaryOp {
+    // 数值越大优先级越高
+    fn precedence(&self) -> u8 {
+        match self {
+            BinaryOp::Or => 1,
+            BinaryOp::And => 2,
+            BinaryOp::Eq
+            | BinaryOp::Ne
+            | BinaryOp::Lt
+            | BinaryOp::Le
+            | BinaryOp::Gt
+            | BinaryOp::Ge => 3,
+            BinaryOp::BitOr => 4,
+            BinaryOp::BitXor => 5,
+            BinaryOp::BitAnd => 6,
+            BinaryOp::Add | BinaryOp::Sub => 7,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => 8,
+        }
+    }
+
+    fn apply(&self, left: f64, right: f64) -> Result<f64, String> {
+        let truth = |value: bool| if value { 1.0 } else { 0.0 };
+        let value = match self {
+            BinaryOp::Or => truth(left != 0.0 || right != 0.0),
+            BinaryOp::And => truth(left != 0.0 && right != 0.0),
+            BinaryOp::Eq => truth(left == right),
+            BinaryOp::Ne => truth(left != right),
+            BinaryOp::Lt => truth(left < right),
+            BinaryOp::Le => truth(left <= right),
+            BinaryOp::Gt => truth(left > right),
+            BinaryOp::Ge => truth(left >= right),
+            // 位运算按整数处理，小数部分直接截断
+            BinaryOp::BitOr => ((left as i64) | (right as i64)) as f64,
+            BinaryOp::BitXor => ((left as i64) ^ (right as i64)) as f64,
+            BinaryOp::BitAnd => ((left as i64) & (right as i64)) as f64,
+            BinaryOp::Add => left + right,
+            BinaryOp::Sub => left - right,
+            BinaryOp::Mul => left * right,
+            BinaryOp::Div | BinaryOp::Rem if right == 0.0 => return Err("除数为零".to_string()),
+            BinaryOp::Div => left / right,
+            BinaryOp::Rem => left % right,
+        };
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(BinaryOp),
+    Not,
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) {
+            let start = i;
+            if c == '0' && matches!(chars.get(i + 1), Some('x') | Some('X')) {
+                i += 2;
+                while i < chars.len() && chars[i].is_ascii_hexdigit() {
+                    i += 1;
+                }
+                let hex: String = chars[start + 2..i].iter().collect();
+                let value = i64::from_str_radix(&hex, 16)
+                    .map_err(|_| format!("无效的十六进制数: 0x{}", hex))?;
+                tokens.push(Token::Number(value as f64));
+            } else {
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let number: String = chars[start..i].iter().collect();
+                let value = number
+                    .parse()
+                    .map_err(|_| format!("无效的数字: {}", number))?;
+                tokens.push(Token::Number(value));
+            }
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.')
+            {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            continue;
+        }
+
+        let next = chars.get(i + 1).copied();
+        let (token, width) = match (c, next) {
+            ('|', Some('|')) => (Token::Op(BinaryOp::Or), 2),
+            ('&', Some('&')) => (Token::Op(BinaryOp::And), 2),
+            ('=', Some('=')) => (Token::Op(BinaryOp::Eq), 2),
+            ('!', Some('=')) => (Token::Op(BinaryOp::Ne), 2),
+            ('<', Some('=')) => (Token::Op(BinaryOp::Le), 2),
+            ('>', Some('=')) => (Token::Op(BinaryOp::Ge), 2),
+            ('<', _) => (Token::Op(BinaryOp::Lt), 1),
+            ('>', _) => (Token::Op(BinaryOp::Gt), 1),
+            ('|', _) => (Token::Op(BinaryOp::BitOr), 1),
+            ('^', _) => (Token::Op(BinaryOp::BitXor), 1),
+            ('&', _) => (Token::Op(BinaryOp::BitAnd), 1),
+            ('+', _) => (Token::Op(BinaryOp::Add), 1),
+            ('-', _) => (Token::Op(BinaryOp::Sub), 1),
+            ('*', _) => (Token::Op(BinaryOp::Mul), 1),
+            ('/', _) => (Token::Op(BinaryOp::Div), 1),
+            ('%', _) => (Token::Op(BinaryOp::Rem), 1),
+            ('!', _) => (Token::Not, 1),
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            (',', _) => (Token::Comma, 1),
+            _ => return Err(format!("无法识别的字符: {}", c)),
+        };
+        tokens.push(token);
+        i += width;
+    }
+    Ok(tokens)
+}
+
+/// 解析后的表达式
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Number(f64),
+    Var(String),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("期望 {:?}，实际为 {:?}", expected, token)),
+            None => Err(format!("表达式不完整，缺少 {:?}", expected)),
+        }
+    }
+
+    // 按优先级爬升解析二元运算
+    fn binary(&mut self, min_precedence: u8) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            if op.precedence() < min_precedence {
+                break;
+            }
+            self.pos += 1;
+            let right = self.binary(op.precedence() + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some(Token::Op(BinaryOp::Sub)) => {
+                self.pos += 1;
+                Ok(Expr::Neg(Box::new(self.unary()?)))
+            }
+            Some(Token::Not) => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                self.pos += 1;
+                let mut args = Vec::new();
+                if self.peek() != Some(&Token::RParen) {
+                    loop {
+                        args.push(self.binary(1)?);
+                        if self.peek() != Some(&Token::Comma) {
+                            break;
+                        }
+                        self.pos += 1;
+                    }
+                }
+                self.expect(Token::RParen)?;
+                match arity(&name) {
+                    None => Err(format!("未知函数: {}", name)),
+                    Some(count) if count != args.len() => Err(format!(
+                        "函数 {} 需要 {} 个参数，实际为 {} 个",
+                        name,
+                        count,
+                        args.len()
+                    )),
+                    Some(_) => Ok(Expr::Call(name, args)),
+                }
+            }
+            Some(Token::Ident(name)) => Ok(Expr::Var(name)),
+            Some(Token::LParen) => {
+                let expr = self.binary(1)?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Some(token) => Err(format!("意外的符号: {:?}", token)),
+            None => Err("表达式不完整".to_string()),
+        }
+    }
+}
+
+// 内置函数的参数个数，解析时据此检查调用
+fn arity(name: &str) -> Option<usize> {
+    match name {
+        "abs" | "sqrt" | "round" | "floor" | "ceil" => Some(1),
+        "min" | "max" => Some(2),
+        "if" => Some(3),
+        _ => None,
+    }
+}
+
+fn call(name: &str, args: &[f64]) -> Result<f64, String> {
+    let value = match (name, args) {
+        ("abs", [x]) => x.abs(),
+        ("sqrt", [x]) => x.sqrt(),
+        ("round", [x]) => x.round(),
+        ("floor", [x]) => x.floor(),
+        ("ceil", [x]) => x.ceil(),
+        ("min", [x, y]) => x.min(*y),
+        ("max", [x, y]) => x.max(*y),
+        ("if", [condition, then, otherwise]) => {
+            if *condition != 0.0 {
+                *then
+            } else {
+                *otherwise
+            }
+        }
+        _ => {
+            return Err(format!(
+                "未知函数或参数数量错误: {}({} 个参数)",
+                name,
+                args.len()
+            ))
+        }
+    };
+    Ok(value)
+}
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Expr, String> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            pos: 0,
+        };
+        let expr = parser.binary(1)?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("意外的符号: {:?}", token));
+        }
+        Ok(expr)
+    }
+
+    /// 表达式引用的标签名，去重后按出现顺序排列
+    pub fn variables(&self) -> Vec<String> {
+        fn collect(expr: &Expr, names: &mut Vec<String>) {
+            match expr {
+                Expr::Number(_) => {}
+                Expr::Var(name) => {
+                    if !names.contains(name) {
+                        names.push(name.clone());
+                    }
+                }
+                Expr::Neg(inner) | Expr::Not(inner) => collect(inner, names),
+                Expr::Binary(_, left, right) => {
+                    collect(left, names);
+                    collect(right, names);
+                }
+                Expr::Call(_, args) => args.iter().for_each(|arg| collect(arg, names)),
+            }
+        }
+        let mut names = Vec::new();
+        collect(self, &mut names);
+        names
+    }
+
+    /// 求值，lookup 返回标签的最新值，缺少任一输入时返回错误
+    pub fn eval(&self, lookup: &dyn Fn(&str) -> Option<f64>) -> Result<f64, String> {
+        match self {
+            Expr::Number(value) => Ok(*value),
+            Expr::Var(name) => lookup(name).ok_or_else(|| format!("标签 {} 暂无数值", name)),
+            Expr::Neg(inner) => Ok(-inner.eval(lookup)?),
+            Expr::Not(inner) => Ok(if inner.eval(lookup)? == 0.0 { 1.0 } else { 0.0 }),
+            Expr::Binary(op, left, right) => op.apply(left.eval(lookup)?, right.eval(lookup)?),
+            Expr::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.eval(lookup))
+                    .collect::<Result<Vec<f64>, String>>()?;
+                call(name, &args)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(text: &str) -> f64 {
+        Expr::parse(text).unwrap().eval(&|_| None).unwrap()
+    }
+
+    fn eval_with(text: &str, name: &str, value: f64) -> f64 {
+        Expr::parse(text)
+            .unwrap()
+            .eval(&|input| (input == name).then_some(value))
+            .unwrap()
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("2 + 3 * 4"), 14.0);
+        assert_eq!(eval("(2 + 3) * 4"), 20.0);
+        assert_eq!(eval("2 - 3 - 4"), -5.0);
+        assert_eq!(eval("20 / 2 / 5"), 2.0);
+        assert_eq!(eval("7 % 4 + 1"), 4.0);
+        assert_eq!(eval("1 || 0 && 0"), 1.0);
+        assert_eq!(eval("1 + 1 == 2 && 3 > 2"), 1.0);
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(eval("-2 * 3"), -6.0);
+        assert_eq!(eval("10 - -2"), 12.0);
+        assert_eq!(eval("--3"), 3.0);
+        assert_eq!(eval("-(1 + 2)"), -3.0);
+        assert_eq!(eval_with("-x + 1", "x", 4.0), -3.0);
+        assert_eq!(eval("!0 + 1"), 2.0);
+    }
+
+    #[test]
+    fn bitwise_binds_tighter_than_comparison() {
+        assert_eq!(eval_with("status & 0x04 != 0", "status", 4.0), 1.0);
+        assert_eq!(eval_with("status & 0x04 != 0", "status", 3.0), 0.0);
+        assert_eq!(eval("1 | 2 == 2"), 0.0);
+        assert_eq!(eval("1 | 2 == 3"), 1.0);
+        assert_eq!(eval("6 ^ 3 & 1"), 7.0);
+        assert_eq!(eval("4 | 1 ^ 1"), 4.0);
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(eval("max(1, 2) + min(3, 4)"), 5.0);
+        assert_eq!(eval("if(2 > 1, 10, 20)"), 10.0);
+        assert_eq!(eval("abs(-2.5)"), 2.5);
+        assert_eq!(
+            Expr::parse("if(a > b, a, c)").unwrap().variables(),
+            vec!["a", "b", "c"]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        for text in [
+            "", "1 +", "(1", "1 )", "1 2", "max(1,)", "1 $ 2", "0x", "1..2",
+        ] {
+            assert!(Expr::parse(text).is_err(), "{} 应当解析失败", text);
+        }
+    }
+
+    #[test]
+    fn unknown_function_and_arity_rejected_when_parsing() {
+        assert!(Expr::parse("foo(1)").is_err());
+        assert!(Expr::parse("abs(1, 2)").is_err());
+        assert!(Expr::parse("max(1)").is_err());
+        assert!(Expr::parse("if(1, 2)").is_err());
+    }
+
+    #[test]
+    fn eval_errors() {
+        let lookup = |_: &str| None;
+        assert!(Expr::parse("1 / 0").unwrap().eval(&lookup).is_err());
+        assert!(Expr::parse("1 % 0").unwrap().eval(&lookup).is_err());
+        assert!(Expr::parse("x + 1").unwrap().eval(&lookup).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+use crate::plc::{DataType, PLCError};
+
+// (client_id, 是否线圈, 地址)
+type GuardKey = (i64, bool, u16);
+
+/// 单个地址的写入限制
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteLimit {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    // 非空时只允许写入其中的值
+    #[serde(default)]
+    pub allowed_values: Vec<f64>,
+    // 两次写入之间的最小间隔，为零时不限制
+    #[serde(default)]
+    pub min_interval_ms: u64,
+}
+
+impl WriteLimit {
+    // 检查取值范围，设定了上下限时 NaN 和无穷大一律拒绝
+    fn validate(&self, value: f64) -> Result<(), WriteRejection> {
+        if (self.min.is_some() || self.max.is_some()) && !value.is_finite() {
+            return Err(WriteRejection::NotFinite { value });
+        }
+        if let Some(min) = self.min {
+            if value < min {
+                return Err(WriteRejection::BelowMin { value, min });
+            }
+        }
+        if let Some(max) = self.max {
+            if value > max {
+                return Err(WriteRejection::AboveMax { value, max });
+            }
+        }
+        if !self.allowed_values.is_empty() && !self.allowed_values.contains(&value) {
+            return Err(WriteRejection::NotAllowed {
+                value,
+                allowed_values: self.allowed_values.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// 写入被拒绝的原因
+#[derive(Error, Debug, Clone, Serialize)]
+#[serde(tag = "reason", rename_all = "camelCase")]
+pub enum WriteRejection {
+    #[error("写入值 {value} 小于下限 {min}")]
+    BelowMin { value: f64, min: f64 },
+
+    #[error("写入值 {value} 大于上限 {max}")]
+    AboveMax { value: f64, max: f64 },
+
+    #[error("写入值 {value} 不是有效的数值")]
+    NotFinite { value: f64 },
+
+    #[error("写入值 {value} 不在允许的取值中")]
+    NotAllowed {
+        value: f64,
+        #[serde(rename = "allowedValues")]
+        allowed_values: Vec<f64>,
+    },
+
+    #[error("写入过于频繁，请 {retry_after_ms}ms 后重试")]
+    TooFrequent {
+        #[serde(rename = "retryAfterMs")]
+        retry_after_ms: u64,
+    },
+}
+
+/// 写入命令返回给前端的错误
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum WriteError {
+    Rejected {
+        message: String,
+        rejection: WriteRejection,
+    },
+    // 写入成功但回读值与写入值不一致
+    VerifyFailed {
+        message: String,
+        address: u16,
+        expected: f64,
+        actual: f64,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl From<WriteRejection> for WriteError {
+    fn from(rejection: WriteRejection) -> Self {
+        WriteError::Rejected {
+            message: rejection.to_string(),
+            rejection,
+        }
+    }
+}
+
+impl From<PLCError> for WriteError {
+    fn from(error: PLCError) -> Self {
+        match error {
+            PLCError::VerifyFailed {
+                address,
+                expected,
+                actual,
+                ..
+            } => WriteError::VerifyFailed {
+                message: error.to_string(),
+                address,
+                expected,
+                actual,
+            },
+            error => WriteError::Failed {
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+impl From<String> for WriteError {
+    fn from(message: String) -> Self {
+        WriteError::Failed { message }
+    }
+}
+
+#[derive(Default)]
+struct GuardState {
+    limit: WriteLimit,
+    last_write: Option<Instant>,
+}
+
+lazy_static! {
+    static ref WRITE_GUARDS: Mutex<HashMap<GuardKey, GuardState>> = Mutex::new(HashMap::new());
+}
+
+fn guard_key(client_id: i64, address: u16, data_type: DataType) -> GuardKey {
+    (client_id, data_type == DataType::Bool, address)
+}
+
+pub async fn set_limit(client_id: i64, address: u16, data_type: DataType, limit: WriteLimit) {
+    let mut guards = WRITE_GUARDS.lock().await;
+    let state = guards
+        .entry(guard_key(client_id, address, data_type))
+        .or_default();
+    state.limit = limit;
+}
+
+pub async fn clear_limit(client_id: i64, address: u16, data_type: DataType) -> bool {
+    WRITE_GUARDS
+        .lock()
+        .await
+        .remove(&guard_key(client_id, address, data_type))
+        .is_some()
+}
+
+/// 检查写入是否符合限制，不记录写入时间，写入成功后需调用 commit
+pub async fn check(
+    client_id: i64,
+    address: u16,
+    data_type: DataType,
+    value: f64,
+) -> Result<(), WriteRejection> {
+    let guards = WRITE_GUARDS.lock().await;
+    let Some(state) = guards.get(&guard_key(client_id, address, data_type)) else {
+        return Ok(());
+    };
+    state.limit.validate(value)?;
+
+    if let Some(last_write) = state.last_write {
+        let min_interval = Duration::from_millis(state.limit.min_interval_ms);
+        let elapsed = last_write.elapsed();
+        if elapsed < min_interval {
+            return Err(WriteRejection::TooFrequent {
+                retry_after_ms: (min_interval - elapsed).as_millis() as u64,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// 记录一次成功的写入，用于最小写入间隔的判断
+pub async fn commit(client_id: i64, address: u16, data_type: DataType) {
+    let mut guards = WRITE_GUARDS.lock().await;
+    if let Some(state) = guards.get_mut(&guard_key(client_id, address, data_type)) {
+        state.last_write = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_finite_values_rejected_when_limited() {
+        let limit = WriteLimit {
+            min: Some(0.0),
+            max: Some(100.0),
+            ..WriteLimit::default()
+        };
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(matches!(
+                limit.validate(value),
+                Err(WriteRejection::NotFinite { .. })
+            ));
+        }
+        let only_max = WriteLimit {
+            max: Some(100.0),
+            ..WriteLimit::default()
+        };
+        assert!(only_max.validate(f64::NAN).is_err());
+        assert!(limit.validate(50.0).is_ok());
+    }
+
+    #[test]
+    fn range_limits() {
+        let limit = WriteLimit {
+            min: Some(0.0),
+            max: Some(100.0),
+            ..WriteLimit::default()
+        };
+        assert!(matches!(
+            limit.validate(-1.0),
+            Err(WriteRejection::BelowMin { .. })
+        ));
+        assert!(matches!(
+            limit.validate(101.0),
+            Err(WriteRejection::AboveMax { .. })
+        ));
+        assert!(limit.validate(0.0).is_ok());
+        assert!(limit.validate(100.0).is_ok());
+    }
+}
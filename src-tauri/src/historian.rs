@@ -0,0 +1,404 @@
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs::{self, File};
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::{mpsc, Mutex};
+use tokio::time;
+
+use crate::plc::{
+    generate_task_key, unix_millis, DataType, PLCError, RegisterTable, TaskDefinition, TaskKey,
+};
+use crate::stats::Metric;
+
+type Result<T> = std::result::Result<T, PLCError>;
+
+// 每个分段文件覆盖一小时的数据，过期时按整个文件删除
+const SEGMENT_MS: u64 = 3_600_000;
+
+// 清理过期分段的间隔
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+// 序列目录中记录保留时长的文件，重启后据此继续清理过期数据
+const RETENTION_FILE: &str = "retention";
+
+// 缓冲数据写入磁盘的间隔
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+// 单次查询最多返回的原始样本数
+const MAX_QUERY_SAMPLES: usize = 100_000;
+
+/// 历史查询结果中的一个点，指定聚合周期时返回各周期的统计值
+#[derive(Serialize, Clone)]
+#[serde(untagged, rename_all = "camelCase")]
+pub enum HistoryPoint {
+    Raw {
+        timestamp: u64,
+        value: f64,
+    },
+    Aggregate {
+        timestamp: u64,
+        #[serde(flatten)]
+        metric: Metric,
+    },
+}
+
+struct Series {
+    directory: PathBuf,
+}
+
+#[derive(Default)]
+struct Historian {
+    directory: Option<PathBuf>,
+    // 正在记录的任务
+    series: HashMap<TaskKey, Series>,
+    // 各序列目录的保留时长，停止记录后仍保留，直到数据全部过期
+    retention: HashMap<PathBuf, u64>,
+}
+
+// 调度器推送的一条样本，由后台写入任务落盘
+struct Sample {
+    task_key: TaskKey,
+    value: f64,
+    timestamp: u64,
+}
+
+// 正在追加的分段文件
+struct OpenSegment {
+    start: u64,
+    writer: BufWriter<File>,
+}
+
+lazy_static! {
+    static ref HISTORIAN: Mutex<Historian> = Mutex::new(Historian::default());
+}
+
+static WRITER: std::sync::Mutex<Option<mpsc::UnboundedSender<Sample>>> =
+    std::sync::Mutex::new(None);
+
+// 每个任务的历史数据单独存放一个目录
+fn series_name(client_id: i64, table: RegisterTable, address: u16) -> String {
+    let table = match table {
+        RegisterTable::Coil => "coil",
+        RegisterTable::Holding => "holding",
+        RegisterTable::Input => "input",
+    };
+    format!("{}_{}_{}", client_id, table, address)
+}
+
+fn segment_start(timestamp: u64) -> u64 {
+    timestamp - timestamp % SEGMENT_MS
+}
+
+// 找出目录下已有的序列及其保留时长，没有保留时长文件的目录不做处理
+async fn discover(directory: &Path) -> HashMap<PathBuf, u64> {
+    let mut retention = HashMap::new();
+    let Ok(mut entries) = fs::read_dir(directory).await else {
+        return retention;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let retention_ms = fs::read_to_string(path.join(RETENTION_FILE))
+            .await
+            .ok()
+            .and_then(|text| text.trim().parse::<u64>().ok());
+        if let Some(retention_ms) = retention_ms {
+            retention.insert(path, retention_ms);
+        }
+    }
+    retention
+}
+
+/// 设置历史数据目录并启动后台写入任务，已有序列按其保留时长继续清理
+pub async fn set_directory(directory: PathBuf) {
+    let retention = discover(&directory).await;
+    {
+        let mut historian = HISTORIAN.lock().await;
+        historian.directory = Some(directory);
+        historian.retention.extend(retention);
+    }
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    *WRITER.lock().unwrap() = Some(sender);
+    tokio::spawn(run_writer(receiver));
+}
+
+/// 开始记录任务的历史数据，重复调用时更新保留时长
+///
+/// 有标签的任务记录第一个标签的工程值，没有标签的任务记录寄存器的原始值
+pub async fn enable(
+    client_id: i64,
+    address: u16,
+    data_type: DataType,
+    read_only: bool,
+    retention_ms: u64,
+) -> Result<()> {
+    if retention_ms == 0 {
+        return Err(PLCError::Other("保留时长不能为零".to_string()));
+    }
+    let mut historian = HISTORIAN.lock().await;
+    let directory = historian
+        .directory
+        .as_ref()
+        .ok_or_else(|| PLCError::Other("历史数据目录未设置".to_string()))?
+        .join(series_name(
+            client_id,
+            RegisterTable::of(data_type, read_only),
+            address,
+        ));
+    fs::create_dir_all(&directory)
+        .await
+        .map_err(|e| PLCError::Other(format!("创建历史数据目录失败: {}", e)))?;
+    fs::write(directory.join(RETENTION_FILE), retention_ms.to_string())
+        .await
+        .map_err(|e| PLCError::Other(format!("保存保留时长失败: {}", e)))?;
+
+    historian.retention.insert(directory.clone(), retention_ms);
+    historian.series.insert(
+        generate_task_key(client_id, address, data_type, read_only),
+        Series { directory },
+    );
+    Ok(())
+}
+
+/// 停止记录，已保存的数据保留到过期为止
+pub async fn disable(client_id: i64, address: u16, data_type: DataType, read_only: bool) -> bool {
+    let task_key = generate_task_key(client_id, address, data_type, read_only);
+    HISTORIAN.lock().await.series.remove(&task_key).is_some()
+}
+
+/// 停止记录连接的所有任务，已保存的数据保留到过期为止
+pub async fn disable_client(client_id: i64) {
+    HISTORIAN
+        .lock()
+        .await
+        .series
+        .retain(|task_key, _| task_key.0 != client_id);
+}
+
+/// 正在记录的任务的保留时长，用于保存项目
+pub async fn retention_of(task_key: &TaskKey) -> Option<u64> {
+    let historian = HISTORIAN.lock().await;
+    let series = historian.series.get(task_key)?;
+    historian.retention.get(&series.directory).copied()
+}
+
+/// 记录调度器推送的值，只把样本交给后台任务，不在调度器中访问磁盘
+pub fn record(task: &TaskDefinition, value: f64, timestamp: u64) {
+    if let Some(sender) = WRITER.lock().unwrap().as_ref() {
+        let _ = sender.send(Sample {
+            task_key: task.key(),
+            value,
+            timestamp,
+        });
+    }
+}
+
+// 追加一条样本，每行为 "时间戳,值"，跨过分段边界时切换文件
+async fn append(
+    files: &mut HashMap<TaskKey, OpenSegment>,
+    sample: &Sample,
+    directory: &Path,
+) -> std::io::Result<()> {
+    let start = segment_start(sample.timestamp);
+    if let Some(segment) = files.get_mut(&sample.task_key) {
+        if segment.start != start {
+            segment.writer.flush().await?;
+            files.remove(&sample.task_key);
+        }
+    }
+    let segment = match files.entry(sample.task_key) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            let file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(directory.join(format!("{}.log", start)))
+                .await?;
+            entry.insert(OpenSegment {
+                start,
+                writer: BufWriter::new(file),
+            })
+        }
+    };
+    let line = format!("{},{}\n", sample.timestamp, sample.value);
+    segment.writer.write_all(line.as_bytes()).await
+}
+
+// 写入所有缓冲的数据，并关闭已停止记录的任务的文件
+async fn flush(files: &mut HashMap<TaskKey, OpenSegment>) {
+    let recording: HashSet<TaskKey> = HISTORIAN.lock().await.series.keys().copied().collect();
+    for segment in files.values_mut() {
+        if let Err(e) = segment.writer.flush().await {
+            eprintln!("写入历史数据失败: {}", e);
+        }
+    }
+    files.retain(|task_key, _| recording.contains(task_key));
+}
+
+async fn run_writer(mut receiver: mpsc::UnboundedReceiver<Sample>) {
+    let mut files: HashMap<TaskKey, OpenSegment> = HashMap::new();
+    let mut flush_timer = time::interval(FLUSH_INTERVAL);
+    let mut cleanup_timer = time::interval(CLEANUP_INTERVAL);
+    loop {
+        tokio::select! {
+            sample = receiver.recv() => {
+                let Some(sample) = sample else {
+                    break;
+                };
+                // 未启用历史记录的任务直接忽略
+                let directory = match HISTORIAN.lock().await.series.get(&sample.task_key) {
+                    Some(series) => series.directory.clone(),
+                    None => continue,
+                };
+                if let Err(e) = append(&mut files, &sample, &directory).await {
+                    eprintln!("写入历史数据失败: {}", e);
+                }
+            }
+            _ = flush_timer.tick() => flush(&mut files).await,
+            _ = cleanup_timer.tick() => remove_expired().await,
+        }
+    }
+    flush(&mut files).await;
+}
+
+// 删除整段都超出保留时长的分段文件，已停止记录且数据全部过期的目录一并删除
+async fn remove_expired() {
+    let retention: Vec<(PathBuf, u64)> = HISTORIAN
+        .lock()
+        .await
+        .retention
+        .iter()
+        .map(|(directory, retention_ms)| (directory.clone(), *retention_ms))
+        .collect();
+
+    let mut finished = Vec::new();
+    for (directory, retention_ms) in retention {
+        let cutoff = unix_millis().saturating_sub(retention_ms);
+        let segments = segments(&directory).await;
+        let mut remaining = segments.len();
+        for (start, path) in segments {
+            if start + SEGMENT_MS <= cutoff {
+                match fs::remove_file(&path).await {
+                    Ok(()) => remaining -= 1,
+                    Err(e) => eprintln!("删除过期历史数据失败: {}", e),
+                }
+            }
+        }
+        if remaining == 0 {
+            finished.push(directory);
+        }
+    }
+
+    let mut historian = HISTORIAN.lock().await;
+    for directory in finished {
+        let recording = historian
+            .series
+            .values()
+            .any(|series| series.directory == directory);
+        // 持有锁时删除目录，避免与重新启用同一序列冲突
+        if !recording {
+            historian.retention.remove(&directory);
+            if let Err(e) = fs::remove_dir_all(&directory).await {
+                eprintln!("删除历史数据目录失败: {}", e);
+            }
+        }
+    }
+}
+
+// 列出目录中的分段文件及其起始时间
+async fn segments(directory: &Path) -> Vec<(u64, PathBuf)> {
+    let mut segments = Vec::new();
+    let Ok(mut entries) = fs::read_dir(directory).await else {
+        return segments;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let start = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<u64>().ok());
+        if let Some(start) = start {
+            segments.push((start, path));
+        }
+    }
+    segments.sort_by_key(|(start, _)| *start);
+    segments
+}
+
+/// 查询时间范围内的样本，bucket_ms 不为零时按周期聚合为最小、最大和平均值
+pub async fn query(
+    client_id: i64,
+    table: RegisterTable,
+    address: u16,
+    start: u64,
+    end: u64,
+    bucket_ms: u64,
+) -> Result<Vec<HistoryPoint>> {
+    if start > end {
+        return Err(PLCError::Other("开始时间晚于结束时间".to_string()));
+    }
+    let directory = HISTORIAN
+        .lock()
+        .await
+        .directory
+        .as_ref()
+        .ok_or_else(|| PLCError::Other("历史数据目录未设置".to_string()))?
+        .join(series_name(client_id, table, address));
+
+    let mut samples = Vec::new();
+    for (segment, path) in segments(&directory).await {
+        if segment + SEGMENT_MS <= start || segment > end {
+            continue;
+        }
+        let content = fs::read_to_string(&path)
+            .await
+            .map_err(|e| PLCError::Other(format!("读取历史数据失败: {}", e)))?;
+        // 写入中断可能留下不完整的行，解析失败的行直接跳过
+        samples.extend(content.lines().filter_map(|line| {
+            let (timestamp, value) = line.split_once(',')?;
+            let timestamp: u64 = timestamp.parse().ok()?;
+            let value: f64 = value.parse().ok()?;
+            (start..=end)
+                .contains(&timestamp)
+                .then_some((timestamp, value))
+        }));
+        if bucket_ms == 0 && samples.len() > MAX_QUERY_SAMPLES {
+            return Err(PLCError::Other(format!(
+                "样本数超过 {}，请缩小时间范围或使用聚合查询",
+                MAX_QUERY_SAMPLES
+            )));
+        }
+    }
+    samples.sort_by_key(|(timestamp, _)| *timestamp);
+
+    if bucket_ms == 0 {
+        return Ok(samples
+            .into_iter()
+            .map(|(timestamp, value)| HistoryPoint::Raw { timestamp, value })
+            .collect());
+    }
+
+    let mut points: Vec<HistoryPoint> = Vec::new();
+    for (timestamp, value) in samples {
+        let bucket = start + (timestamp - start) / bucket_ms * bucket_ms;
+        match points.last_mut() {
+            Some(HistoryPoint::Aggregate {
+                timestamp: current,
+                metric,
+            }) if *current == bucket => metric.add(value),
+            _ => {
+                let mut metric = Metric::default();
+                metric.add(value);
+                points.push(HistoryPoint::Aggregate {
+                    timestamp: bucket,
+                    metric,
+                });
+            }
+        }
+    }
+    Ok(points)
+}
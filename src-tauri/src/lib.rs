@@ -1,8 +1,22 @@
+mod alarm;
 mod command;
+mod expr;
+mod guard;
+mod historian;
+mod logger;
 mod modbus;
 // mod modbus_tcp;
 mod notice;
+mod optimizer;
 mod plc;
+mod project;
+mod register_map;
+mod script;
+mod stats;
+mod tags;
+mod writer;
+
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -21,7 +35,11 @@ pub fn run() {
             let app_handle = app.handle();
             notice::set_app(app_handle.clone());
 
+            // 历史数据保存在应用数据目录下
+            let history_dir = app_handle.path().app_data_dir()?.join("history");
+
             tauri::async_runtime::block_on(async {
+                historian::set_directory(history_dir).await;
                 if let Err(e) = plc::initialize().await {
                     eprintln!("任务调度器启动失败: {}", e);
                 }
@@ -29,15 +47,44 @@ pub fn run() {
 
             Ok(())
         })
+        .on_window_event(|window, event| {
+            // 窗口关闭后不再向其推送数据
+            if let tauri::WindowEvent::Destroyed = event {
+                notice::remove_subscriber(window.label());
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             command::modbus_create_tcp_connection,
             command::modbus_create_serial_connection,
             command::modbus_disconnect,
             command::modbus_connection_exists,
+            command::modbus_set_disconnect_policy,
+            command::modbus_set_queue_depth,
+            command::modbus_set_request_timeout,
             command::plc_stop,
             command::plc_start,
             command::plc_register_task,
             command::plc_unregister_task,
+            command::plc_set_task_interval,
+            command::plc_pause_task,
+            command::plc_resume_task,
+            command::plc_pause_client_tasks,
+            command::plc_resume_client_tasks,
+            command::plc_schedule_read,
+            command::plc_register_trigger,
+            command::plc_unregister_trigger,
+            command::plc_set_backoff,
+            command::plc_set_read_optimizer,
+            command::plc_set_task_deadband,
+            command::plc_set_integrity_interval,
+            command::plc_get_task_status,
+            command::plc_get_scheduler_stats,
+            command::plc_reset_scheduler_stats,
+            command::plc_set_stats_interval,
+            command::plc_set_batch_mode,
+            command::plc_subscribe,
+            command::plc_unsubscribe,
+            command::plc_clear_subscriptions,
             command::get_serial_ports,
             command::plc_read_bool,
             command::plc_read_word,
@@ -47,6 +94,36 @@ pub fn run() {
             command::plc_write_word,
             command::plc_write_dword,
             command::plc_write_float,
+            command::plc_write_batch,
+            command::plc_set_write_limit,
+            command::plc_clear_write_limit,
+            command::plc_define_tag,
+            command::plc_remove_tag,
+            command::plc_list_tags,
+            command::plc_define_virtual_tag,
+            command::plc_remove_virtual_tag,
+            command::plc_list_virtual_tags,
+            command::plc_read_tag,
+            command::plc_write_tag,
+            command::plc_get_recent_values,
+            command::project_save,
+            command::project_load,
+            command::plc_import_csv,
+            command::plc_export_csv,
+            command::historian_enable,
+            command::historian_disable,
+            command::historian_query,
+            command::logger_start,
+            command::logger_stop,
+            command::logger_status,
+            command::alarm_define,
+            command::alarm_remove,
+            command::alarm_list,
+            command::alarm_acknowledge,
+            command::alarm_history,
+            command::script_run,
+            command::script_stop,
+            command::script_list,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
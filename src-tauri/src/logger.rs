@@ -0,0 +1,239 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::fs::{self, File};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time;
+
+use crate::plc::{unix_millis, PLCError};
+use crate::tags;
+
+type Result<T> = std::result::Result<T, PLCError>;
+
+/// 数据记录配置
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoggerConfig {
+    // 按此顺序输出为 CSV 的列
+    pub tags: Vec<String>,
+    pub sample_period_ms: u64,
+    pub directory: String,
+    // 文件超过该大小后切换新文件，为零时不按大小切换
+    #[serde(default)]
+    pub max_file_bytes: u64,
+    // 文件写入超过该时长后切换新文件，为零时不按时间切换
+    #[serde(default)]
+    pub max_file_age_ms: u64,
+}
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LoggerStatus {
+    pub running: bool,
+    pub tags: Vec<String>,
+    pub file: Option<String>,
+    pub rows_written: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Default)]
+struct Logger {
+    handle: Option<JoinHandle<()>>,
+    // 通知记录任务写完缓冲数据后退出
+    stop: Option<oneshot::Sender<()>>,
+    status: LoggerStatus,
+}
+
+lazy_static! {
+    static ref LOGGER: Mutex<Logger> = Mutex::new(Logger::default());
+    // 各标签的最新值，记录器未运行时为 None
+    static ref LATEST: Mutex<Option<HashMap<String, Option<f64>>>> = Mutex::new(None);
+}
+
+/// 由调度器推送标签值，读取失败时传入 None
+pub async fn update(name: &str, value: Option<f64>) {
+    if let Some(latest) = LATEST.lock().await.as_mut() {
+        if let Some(slot) = latest.get_mut(name) {
+            *slot = value;
+        }
+    }
+}
+
+// 按 CSV 规则编码一行，标签名中的逗号和引号会被转义
+fn encode_record<I, T>(fields: I) -> Result<Vec<u8>>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<[u8]>,
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(fields)
+        .map_err(|e| PLCError::Other(format!("生成记录行失败: {}", e)))?;
+    writer
+        .into_inner()
+        .map_err(|e| PLCError::Other(format!("生成记录行失败: {}", e)))
+}
+
+struct LogFile {
+    file: File,
+    bytes: u64,
+    opened: Instant,
+}
+
+impl LogFile {
+    async fn create(config: &LoggerConfig) -> Result<(PathBuf, LogFile)> {
+        let path = PathBuf::from(&config.directory).join(format!("log_{}.csv", unix_millis()));
+        let mut file = File::create(&path)
+            .await
+            .map_err(|e| PLCError::Other(format!("创建记录文件失败: {}", e)))?;
+        let header = encode_record(
+            std::iter::once("timestamp").chain(config.tags.iter().map(String::as_str)),
+        )?;
+        file.write_all(&header)
+            .await
+            .map_err(|e| PLCError::Other(format!("写入记录文件失败: {}", e)))?;
+
+        let log_file = LogFile {
+            file,
+            bytes: header.len() as u64,
+            opened: Instant::now(),
+        };
+        Ok((path, log_file))
+    }
+
+    fn should_rotate(&self, config: &LoggerConfig) -> bool {
+        (config.max_file_bytes > 0 && self.bytes >= config.max_file_bytes)
+            || (config.max_file_age_ms > 0
+                && self.opened.elapsed() >= Duration::from_millis(config.max_file_age_ms))
+    }
+}
+
+// 按标签顺序生成一行，没有有效值的标签留空
+async fn sample_row(config: &LoggerConfig) -> Result<Vec<u8>> {
+    let latest = LATEST.lock().await;
+    let mut fields = vec![unix_millis().to_string()];
+    for name in &config.tags {
+        match latest.as_ref().and_then(|latest| latest.get(name)) {
+            Some(Some(value)) => fields.push(value.to_string()),
+            _ => fields.push(String::new()),
+        }
+    }
+    encode_record(&fields)
+}
+
+async fn write_sample(config: &LoggerConfig, log_file: &mut LogFile) {
+    if log_file.should_rotate(config) {
+        // 切换前写完旧文件的缓冲数据
+        if let Err(e) = log_file.file.flush().await {
+            LOGGER.lock().await.status.last_error = Some(format!("写入记录文件失败: {}", e));
+        }
+        match LogFile::create(config).await {
+            Ok((path, new_file)) => {
+                *log_file = new_file;
+                LOGGER.lock().await.status.file = Some(path.display().to_string());
+            }
+            Err(e) => LOGGER.lock().await.status.last_error = Some(e.to_string()),
+        }
+    }
+
+    let row = match sample_row(config).await {
+        Ok(row) => row,
+        Err(e) => {
+            LOGGER.lock().await.status.last_error = Some(e.to_string());
+            return;
+        }
+    };
+    let result = log_file.file.write_all(&row).await;
+    let mut logger = LOGGER.lock().await;
+    match result {
+        Ok(()) => {
+            log_file.bytes += row.len() as u64;
+            logger.status.rows_written += 1;
+        }
+        Err(e) => logger.status.last_error = Some(format!("写入记录文件失败: {}", e)),
+    }
+}
+
+async fn run(config: LoggerConfig, mut log_file: LogFile, mut stop: oneshot::Receiver<()>) {
+    let mut interval = time::interval(Duration::from_millis(config.sample_period_ms));
+    interval.set_missed_tick_behavior(time::MissedTickBehavior::Skip);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => write_sample(&config, &mut log_file).await,
+            _ = &mut stop => break,
+        }
+    }
+
+    if let Err(e) = log_file.file.flush().await {
+        LOGGER.lock().await.status.last_error = Some(format!("写入记录文件失败: {}", e));
+    }
+}
+
+pub async fn start(config: LoggerConfig) -> Result<()> {
+    let mut logger = LOGGER.lock().await;
+    if logger.handle.is_some() {
+        return Err(PLCError::Other("数据记录已在运行".to_string()));
+    }
+    if config.sample_period_ms == 0 {
+        return Err(PLCError::Other("采样周期不能为零".to_string()));
+    }
+    if config.tags.is_empty() {
+        return Err(PLCError::Other("未选择要记录的标签".to_string()));
+    }
+    for name in &config.tags {
+        if !tags::tag_exists(name).await {
+            return Err(PLCError::TagNotFound(name.clone()));
+        }
+    }
+
+    fs::create_dir_all(&config.directory)
+        .await
+        .map_err(|e| PLCError::Other(format!("创建记录目录失败: {}", e)))?;
+    let (path, log_file) = LogFile::create(&config).await?;
+
+    // 以当前值开始，数值不变的标签也能记录
+    *LATEST.lock().await = Some(tags::latest_values(&config.tags).await);
+    logger.status = LoggerStatus {
+        running: true,
+        tags: config.tags.clone(),
+        file: Some(path.display().to_string()),
+        rows_written: 0,
+        last_error: None,
+    };
+    let (stop, stopped) = oneshot::channel();
+    logger.stop = Some(stop);
+    logger.handle = Some(tokio::spawn(run(config, log_file, stopped)));
+    Ok(())
+}
+
+pub async fn stop() -> Result<()> {
+    let (handle, stop) = {
+        let mut logger = LOGGER.lock().await;
+        let handle = logger
+            .handle
+            .take()
+            .ok_or_else(|| PLCError::Other("数据记录未运行".to_string()))?;
+        (handle, logger.stop.take())
+    };
+    // 记录任务退出前会更新状态，等待时不能持有锁
+    if let Some(stop) = stop {
+        let _ = stop.send(());
+    }
+    let _ = handle.await;
+
+    // 等待期间可能已重新启动
+    let mut logger = LOGGER.lock().await;
+    if logger.handle.is_none() {
+        logger.status.running = false;
+        *LATEST.lock().await = None;
+    }
+    Ok(())
+}
+
+pub async fn status() -> LoggerStatus {
+    LOGGER.lock().await.status.clone()
+}
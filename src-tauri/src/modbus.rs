@@ -1,14 +1,18 @@
 use dns_lookup::lookup_host;
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::future::Future;
 use std::hash::{Hash, Hasher};
 use std::net::{Ipv4Addr, SocketAddr};
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
 use thiserror::Error;
-use tokio::sync::Mutex;
+use tokio::sync::{oneshot, Mutex};
 use tokio_modbus::prelude::*;
 
 #[derive(Error, Debug)]
@@ -22,6 +26,18 @@ pub enum ModbusError {
     #[error("未找到 ID 为 {0} 的连接")]
     ClientNotFound(i64),
 
+    #[error("设备异常响应: {0}")]
+    Exception(String),
+
+    #[error("连接 {0} 的请求队列已满")]
+    QueueFull(i64),
+
+    #[error("轮询请求等待超时，已丢弃")]
+    RequestDropped,
+
+    #[error("请求超时")]
+    Timeout,
+
     #[error("{0}")]
     Other(String),
 }
@@ -31,7 +47,12 @@ pub type Result<T> = std::result::Result<T, ModbusError>;
 
 impl From<std::io::Error> for ModbusError {
     fn from(err: std::io::Error) -> Self {
-        ModbusError::Other(err.to_string())
+        // tokio-modbus 未公开异常响应类型，只能通过错误消息识别
+        let message = err.to_string();
+        if err.kind() == std::io::ErrorKind::Other && message.starts_with("Modbus function ") {
+            return ModbusError::Exception(message);
+        }
+        ModbusError::Other(message)
     }
 }
 
@@ -47,6 +68,12 @@ impl From<serialport::Error> for ModbusError {
     }
 }
 
+// 默认每个连接最多排队的请求数
+const DEFAULT_MAX_QUEUE_DEPTH: usize = 32;
+
+// 默认请求超时时间，设备无响应时避免一直占用连接
+const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 3_000;
+
 lazy_static! {
     pub static ref MODBUS_MANAGER: ModbusManager = ModbusManager::new();
 }
@@ -119,15 +146,217 @@ fn generate_serial_key(serial_port_str: &str) -> Result<i64> {
     Ok(hasher.finish() as i64)
 }
 
+/// 请求优先级，写入和单次读取优先于周期轮询
+#[derive(Debug, Clone, Copy)]
+pub enum Priority {
+    // 周期轮询，排队超过 max_wait 后丢弃，下个周期会重新请求
+    Poll { max_wait: Duration },
+    Read,
+    Write,
+}
+
+impl Priority {
+    fn rank(&self) -> u8 {
+        match self {
+            Priority::Poll { .. } => 0,
+            Priority::Read => 1,
+            Priority::Write => 2,
+        }
+    }
+}
+
+struct Waiter {
+    rank: u8,
+    seq: u64,
+    enqueued: Instant,
+    max_wait: Option<Duration>,
+    sender: oneshot::Sender<QueuePermit>,
+}
+
+impl Waiter {
+    fn is_stale(&self) -> bool {
+        self.max_wait
+            .is_some_and(|max_wait| self.enqueued.elapsed() > max_wait)
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    busy: bool,
+    seq: u64,
+    waiting: Vec<Waiter>,
+}
+
+/// 单个连接的优先级请求队列，同一时间只放行一个请求
+struct RequestQueue {
+    client_id: i64,
+    state: StdMutex<QueueState>,
+}
+
+// 持有期间独占连接，释放时放行队列中优先级最高的请求
+struct QueuePermit {
+    queue: Option<Arc<RequestQueue>>,
+}
+
+impl Drop for QueuePermit {
+    fn drop(&mut self) {
+        if let Some(queue) = self.queue.take() {
+            queue.release();
+        }
+    }
+}
+
+impl RequestQueue {
+    async fn acquire(
+        self: &Arc<Self>,
+        priority: Priority,
+        max_depth: usize,
+    ) -> Result<QueuePermit> {
+        let receiver = {
+            let mut state = self.state.lock().unwrap();
+            if !state.busy {
+                state.busy = true;
+                return Ok(QueuePermit {
+                    queue: Some(self.clone()),
+                });
+            }
+
+            // 队列已满时丢弃最早的轮询请求，为写入和单次读取腾出位置
+            if state.waiting.len() >= max_depth {
+                let oldest_poll = state
+                    .waiting
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, waiter)| waiter.max_wait.is_some())
+                    .min_by_key(|(_, waiter)| waiter.seq)
+                    .map(|(index, _)| index);
+                match (priority, oldest_poll) {
+                    (Priority::Poll { .. }, _) | (_, None) => {
+                        return Err(ModbusError::QueueFull(self.client_id));
+                    }
+                    (_, Some(index)) => {
+                        state.waiting.swap_remove(index);
+                    }
+                }
+            }
+
+            let (sender, receiver) = oneshot::channel();
+            state.seq += 1;
+            let seq = state.seq;
+            state.waiting.push(Waiter {
+                rank: priority.rank(),
+                seq,
+                enqueued: Instant::now(),
+                max_wait: match priority {
+                    Priority::Poll { max_wait } => Some(max_wait),
+                    _ => None,
+                },
+                sender,
+            });
+            receiver
+        };
+
+        receiver.await.map_err(|_| ModbusError::RequestDropped)
+    }
+
+    fn release(self: Arc<Self>) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            let next = state
+                .waiting
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, waiter)| (waiter.rank, Reverse(waiter.seq)))
+                .map(|(index, _)| index);
+            let Some(index) = next else {
+                state.busy = false;
+                return;
+            };
+
+            // 丢弃等待过久的轮询请求，发送端被释放后等待方收到 RequestDropped
+            let waiter = state.waiting.swap_remove(index);
+            if waiter.is_stale() {
+                continue;
+            }
+
+            let permit = QueuePermit {
+                queue: Some(self.clone()),
+            };
+            match waiter.sender.send(permit) {
+                Ok(()) => return,
+                // 等待方已取消，收回许可避免在持锁时重入 release
+                Err(mut permit) => {
+                    permit.queue.take();
+                }
+            }
+        }
+    }
+}
+
+/// 创建连接时使用的参数，用于保存和重建连接
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ConnectionConfig {
+    #[serde(rename_all = "camelCase")]
+    Tcp { ip: String, port: u16 },
+    #[serde(rename_all = "camelCase")]
+    Serial {
+        serial_port: String,
+        baud_rate: u32,
+        slave_id: u8,
+    },
+}
+
+#[derive(Clone)]
+struct Connection {
+    context: Arc<Mutex<client::Context>>,
+    queue: Arc<RequestQueue>,
+    config: ConnectionConfig,
+}
+
+impl Connection {
+    fn new(client_id: i64, config: ConnectionConfig, context: client::Context) -> Self {
+        Connection {
+            config,
+            context: Arc::new(Mutex::new(context)),
+            queue: Arc::new(RequestQueue {
+                client_id,
+                state: StdMutex::new(QueueState::default()),
+            }),
+        }
+    }
+}
+
 pub struct ModbusManager {
-    clients: Mutex<HashMap<i64, Arc<Mutex<client::Context>>>>,
+    clients: Mutex<HashMap<i64, Connection>>,
+    max_queue_depth: Mutex<usize>,
+    request_timeout: Mutex<Duration>,
 }
 
 impl ModbusManager {
     pub fn new() -> Self {
         ModbusManager {
             clients: Mutex::new(HashMap::new()),
+            max_queue_depth: Mutex::new(DEFAULT_MAX_QUEUE_DEPTH),
+            request_timeout: Mutex::new(Duration::from_millis(DEFAULT_REQUEST_TIMEOUT_MS)),
+        }
+    }
+
+    pub async fn set_request_timeout(&self, timeout_ms: u64) -> Result<()> {
+        if timeout_ms == 0 {
+            return Err(ModbusError::Other("超时时间不能为零".to_string()));
         }
+        *self.request_timeout.lock().await = Duration::from_millis(timeout_ms);
+        Ok(())
+    }
+
+    // 设置每个连接最多排队的请求数
+    pub async fn set_max_queue_depth(&self, depth: usize) -> Result<()> {
+        if depth == 0 {
+            return Err(ModbusError::Other("队列深度不能为零".to_string()));
+        }
+        *self.max_queue_depth.lock().await = depth;
+        Ok(())
     }
 
     // 创建新连接
@@ -143,9 +372,12 @@ impl ModbusManager {
         }
         let socket_addr = SocketAddr::from_str(&format!("{}:{}", ip_str, port))?;
         let client = tcp::connect_slave(socket_addr, Slave(1)).await?;
-        let client = Arc::new(Mutex::new(client));
         let mut clients = self.clients.lock().await;
-        clients.insert(client_id, client);
+        let config = ConnectionConfig::Tcp {
+            ip: ip_str.to_string(),
+            port,
+        };
+        clients.insert(client_id, Connection::new(client_id, config, client));
         Ok(client_id)
     }
 
@@ -186,14 +418,19 @@ impl ModbusManager {
         println!("正在验证串口连接: {}", serial_port_str);
 
         // 将客户端保存到 clients 中
-        let client = Arc::new(Mutex::new(client));
+        let config = ConnectionConfig::Serial {
+            serial_port: serial_port_str.to_string(),
+            baud_rate,
+            slave_id,
+        };
+        let connection = Connection::new(client_id, config, client);
 
         let mut clients = self.clients.lock().await;
-        clients.insert(client_id, client.clone());
+        clients.insert(client_id, connection.clone());
 
         // 验证连接是否可用
-        let mut client_lock = client.lock().await;
-        match client_lock.read_holding_registers(0, 1).await {
+        let mut client_lock = connection.context.lock().await;
+        match self.timed(client_lock.read_holding_registers(0, 1)).await {
             Ok(_) => {
                 #[cfg(debug_assertions)]
                 println!("串口连接验证成功: {}", serial_port_str);
@@ -208,14 +445,38 @@ impl ModbusManager {
         Ok(client_id)
     }
 
+    // 按保存的参数建立连接
+    pub async fn connect(&self, config: &ConnectionConfig) -> Result<i64> {
+        match config {
+            ConnectionConfig::Tcp { ip, port } => self.create_tcp_connection(ip, *port).await,
+            ConnectionConfig::Serial {
+                serial_port,
+                baud_rate,
+                slave_id,
+            } => {
+                self.create_serial_connection(serial_port, *baud_rate, *slave_id)
+                    .await
+            }
+        }
+    }
+
+    // 获取所有连接及其创建参数
+    pub async fn connection_configs(&self) -> Vec<(i64, ConnectionConfig)> {
+        let clients = self.clients.lock().await;
+        clients
+            .iter()
+            .map(|(client_id, connection)| (*client_id, connection.config.clone()))
+            .collect()
+    }
+
     // 断开指定 ID 的连接
     pub async fn disconnect(&self, client_id: i64) -> Result<()> {
         let mut clients = self.clients.lock().await;
 
-        let client = clients
+        let connection = clients
             .remove(&client_id)
             .ok_or_else(|| ModbusError::ClientNotFound(client_id))?;
-        let mut client = client.lock().await;
+        let mut client = connection.context.lock().await;
         client.disconnect().await?;
 
         #[cfg(debug_assertions)]
@@ -230,10 +491,13 @@ impl ModbusManager {
         client_id: i64,
         address: u16,
         quantity: u16,
+        priority: Priority,
     ) -> Result<Vec<u16>> {
-        let client = self.get_client(client_id).await?;
+        let (_permit, client) = self.acquire(client_id, priority).await?;
         let mut client = client.lock().await;
-        let value = client.read_holding_registers(address, quantity).await?;
+        let value = self
+            .timed(client.read_holding_registers(address, quantity))
+            .await?;
         Ok(value)
     }
 
@@ -244,9 +508,10 @@ impl ModbusManager {
         address: u16,
         value: u16,
     ) -> Result<()> {
-        let client = self.get_client(client_id).await?;
+        let (_permit, client) = self.acquire(client_id, Priority::Write).await?;
         let mut client = client.lock().await;
-        client.write_single_register(address, value).await?;
+        self.timed(client.write_single_register(address, value))
+            .await?;
         Ok(())
     }
 
@@ -257,9 +522,10 @@ impl ModbusManager {
         address: u16,
         values: &[u16],
     ) -> Result<()> {
-        let client = self.get_client(client_id).await?;
+        let (_permit, client) = self.acquire(client_id, Priority::Write).await?;
         let mut client = client.lock().await;
-        client.write_multiple_registers(address, values).await?;
+        self.timed(client.write_multiple_registers(address, values))
+            .await?;
         Ok(())
     }
 
@@ -269,10 +535,13 @@ impl ModbusManager {
         client_id: i64,
         address: u16,
         quantity: u16,
+        priority: Priority,
     ) -> Result<Vec<u16>> {
-        let client = self.get_client(client_id).await?;
+        let (_permit, client) = self.acquire(client_id, priority).await?;
         let mut client = client.lock().await;
-        let vlaue = client.read_input_registers(address, quantity).await?;
+        let vlaue = self
+            .timed(client.read_input_registers(address, quantity))
+            .await?;
         Ok(vlaue)
     }
 
@@ -282,18 +551,33 @@ impl ModbusManager {
         client_id: i64,
         address: u16,
         quantity: u16,
+        priority: Priority,
     ) -> Result<Vec<bool>> {
-        let client = self.get_client(client_id).await?;
+        let (_permit, client) = self.acquire(client_id, priority).await?;
         let mut client = client.lock().await;
-        let value = client.read_coils(address, quantity).await?;
+        let value = self.timed(client.read_coils(address, quantity)).await?;
         Ok(value)
     }
 
     // 写入单个线圈
     pub async fn write_single_coil(&self, client_id: i64, address: u16, value: bool) -> Result<()> {
-        let client = self.get_client(client_id).await?;
+        let (_permit, client) = self.acquire(client_id, Priority::Write).await?;
+        let mut client = client.lock().await;
+        self.timed(client.write_single_coil(address, value)).await?;
+        Ok(())
+    }
+
+    // 写入多个线圈
+    pub async fn write_multiple_coils(
+        &self,
+        client_id: i64,
+        address: u16,
+        values: &[bool],
+    ) -> Result<()> {
+        let (_permit, client) = self.acquire(client_id, Priority::Write).await?;
         let mut client = client.lock().await;
-        client.write_single_coil(address, value).await?;
+        self.timed(client.write_multiple_coils(address, values))
+            .await?;
         Ok(())
     }
 
@@ -309,7 +593,7 @@ impl ModbusManager {
         clients.contains_key(&client_id)
     }
 
-    async fn get_client(&self, client_id: i64) -> Result<Arc<Mutex<client::Context>>> {
+    async fn get_client(&self, client_id: i64) -> Result<Connection> {
         let clients = self.clients.lock().await;
         let client = clients
             .get(&client_id)
@@ -317,6 +601,27 @@ impl ModbusManager {
         let client = client.clone();
         Ok(client)
     }
+
+    // 为请求加上超时限制
+    async fn timed<T>(&self, request: impl Future<Output = std::io::Result<T>>) -> Result<T> {
+        let timeout = *self.request_timeout.lock().await;
+        match tokio::time::timeout(timeout, request).await {
+            Ok(result) => Ok(result?),
+            Err(_) => Err(ModbusError::Timeout),
+        }
+    }
+
+    // 按优先级排队获取连接的使用权
+    async fn acquire(
+        &self,
+        client_id: i64,
+        priority: Priority,
+    ) -> Result<(QueuePermit, Arc<Mutex<client::Context>>)> {
+        let connection = self.get_client(client_id).await?;
+        let max_depth = *self.max_queue_depth.lock().await;
+        let permit = connection.queue.acquire(priority, max_depth).await?;
+        Ok((permit, connection.context))
+    }
 }
 
 impl Drop for ModbusManager {
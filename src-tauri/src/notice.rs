@@ -1,6 +1,44 @@
+use lazy_static::lazy_static;
 use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::sync::Mutex;
-use tauri::Emitter;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, EventTarget};
+
+use crate::alarm::AlarmEvent;
+use crate::plc::{DataType, PlcValue, RegisterTable};
+use crate::script::{ScriptInfo, ScriptOutput};
+use crate::stats::SchedulerStats;
+
+// 数据质量
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Quality {
+    #[default]
+    Good,
+    BadComm,
+    BadException,
+    Stale,
+    // 虚拟标签的表达式求值失败
+    BadExpression,
+}
+
+// 设备通信状态
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DeviceState {
+    Online,
+    Backoff,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceStateChange {
+    pub client_id: i64,
+    pub state: DeviceState,
+    pub consecutive_failures: u32,
+    pub timestamp: u64,
+}
 
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -8,6 +46,11 @@ pub struct BoolValue {
     pub client_id: i64,
     pub address: u16,
     pub value: bool,
+    pub quality: Quality,
+    // 响应接收时间（毫秒时间戳）
+    pub timestamp: u64,
+    // 请求耗时（毫秒）
+    pub latency_ms: f64,
 }
 
 #[derive(Serialize, Clone)]
@@ -17,6 +60,9 @@ pub struct WordValue {
     pub address: u16,
     pub read_only: bool,
     pub value: u16,
+    pub quality: Quality,
+    pub timestamp: u64,
+    pub latency_ms: f64,
 }
 
 #[derive(Serialize, Clone)]
@@ -26,6 +72,9 @@ pub struct DwordValue {
     pub address: u16,
     pub read_only: bool,
     pub value: u32,
+    pub quality: Quality,
+    pub timestamp: u64,
+    pub latency_ms: f64,
 }
 
 #[derive(Serialize, Clone)]
@@ -35,10 +84,123 @@ pub struct FloatValue {
     pub address: u16,
     pub read_only: bool,
     pub value: f32,
+    pub quality: Quality,
+    pub timestamp: u64,
+    pub latency_ms: f64,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskError {
+    pub client_id: i64,
+    pub address: u16,
+    pub data_type: u8,
+    pub read_only: bool,
+    pub quality: Quality,
+    pub error: String,
+    pub timestamp: u64,
+}
+
+// 按标签名推送的工程值，读取失败时 value 为空
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TagValue {
+    pub name: String,
+    pub client_id: i64,
+    pub address: u16,
+    pub value: Option<f64>,
+    pub quality: Quality,
+    pub error: Option<String>,
+    pub timestamp: u64,
+}
+
+// 一次性或触发读取的结果
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerRead {
+    pub id: String,
+    pub client_id: i64,
+    pub address: u16,
+    pub data_type: u8,
+    pub read_only: bool,
+    pub values: Vec<PlcValue>,
+    pub quality: Quality,
+    pub error: Option<String>,
+    pub timestamp: u64,
+}
+
+// 批量推送中的单条变化
+#[derive(Serialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ValueUpdate {
+    Bool(BoolValue),
+    Word(WordValue),
+    Dword(DwordValue),
+    Float(FloatValue),
+}
+
+impl ValueUpdate {
+    fn point(&self) -> Point {
+        match self {
+            ValueUpdate::Bool(v) => (v.client_id, RegisterTable::Coil, v.address),
+            ValueUpdate::Word(v) => (v.client_id, register_table(v.read_only), v.address),
+            ValueUpdate::Dword(v) => (v.client_id, register_table(v.read_only), v.address),
+            ValueUpdate::Float(v) => (v.client_id, register_table(v.read_only), v.address),
+        }
+    }
+}
+
+// 事件对应的数据点：连接、数据区和地址，同一偏移的线圈、保持和输入寄存器互不相同
+pub type Point = (i64, RegisterTable, u16);
+
+fn register_table(read_only: bool) -> RegisterTable {
+    RegisterTable::of(DataType::Word, read_only)
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchUpdate {
+    pub changes: Vec<ValueUpdate>,
+}
+
+struct BatchState {
+    enabled: bool,
+    // 为零时每个调度周期推送一次
+    window_ms: u64,
+    pending: Vec<ValueUpdate>,
+    window_start: Option<Instant>,
+}
+
+// 窗口订阅，订阅整个连接或连接下的指定数据点
+#[derive(Default)]
+struct Subscription {
+    clients: HashSet<i64>,
+    points: HashSet<Point>,
+}
+
+impl Subscription {
+    fn matches(&self, point: &Point) -> bool {
+        self.clients.contains(&point.0) || self.points.contains(point)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.clients.is_empty() && self.points.is_empty()
+    }
+}
+
+lazy_static! {
+    // 按窗口标签索引的订阅，没有订阅的窗口仍接收所有事件
+    static ref SUBSCRIPTIONS: Mutex<HashMap<String, Subscription>> = Mutex::new(HashMap::new());
 }
 
 // 添加全局静态变量
 static APP: Mutex<Option<tauri::AppHandle>> = Mutex::new(None);
+static BATCH: Mutex<BatchState> = Mutex::new(BatchState {
+    enabled: false,
+    window_ms: 0,
+    pending: Vec::new(),
+    window_start: None,
+});
 
 // 添加设置方法
 pub fn set_app(app: tauri::AppHandle) {
@@ -54,93 +216,500 @@ fn get_app() -> tauri::AppHandle {
         .expect("App handle not initialized")
 }
 
+// 订阅连接的数据，addresses 为空时订阅该连接的所有地址
+pub fn subscribe(label: &str, client_id: i64, table: RegisterTable, addresses: Option<Vec<u16>>) {
+    let mut subscriptions = SUBSCRIPTIONS.lock().unwrap();
+    let subscription = subscriptions.entry(label.to_string()).or_default();
+    match addresses {
+        Some(addresses) => subscription.points.extend(
+            addresses
+                .into_iter()
+                .map(|address| (client_id, table, address)),
+        ),
+        None => {
+            subscription.clients.insert(client_id);
+        }
+    }
+}
+
+pub fn unsubscribe(label: &str, client_id: i64, table: RegisterTable, addresses: Option<Vec<u16>>) {
+    let mut subscriptions = SUBSCRIPTIONS.lock().unwrap();
+    let Some(subscription) = subscriptions.get_mut(label) else {
+        return;
+    };
+    match addresses {
+        Some(addresses) => {
+            for address in addresses {
+                subscription.points.remove(&(client_id, table, address));
+            }
+        }
+        None => {
+            subscription.clients.remove(&client_id);
+            subscription.points.retain(|(id, _, _)| *id != client_id);
+        }
+    }
+    if subscription.is_empty() {
+        subscriptions.remove(label);
+    }
+}
+
+// 清除窗口的全部订阅，窗口关闭时调用
+pub fn remove_subscriber(label: &str) {
+    SUBSCRIPTIONS.lock().unwrap().remove(label);
+}
+
+// 有订阅但未订阅该数据点的窗口，这些窗口不接收该事件
+fn excluded_windows(point: &Point) -> HashSet<String> {
+    SUBSCRIPTIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, subscription)| !subscription.matches(point))
+        .map(|(label, _)| label.clone())
+        .collect()
+}
+
+fn target_label(target: &EventTarget) -> Option<&str> {
+    match target {
+        EventTarget::AnyLabel { label }
+        | EventTarget::Window { label }
+        | EventTarget::Webview { label }
+        | EventTarget::WebviewWindow { label } => Some(label),
+        _ => None,
+    }
+}
+
+// 推送事件，point 为空时广播；没有订阅的窗口始终接收，有订阅的窗口只接收其订阅的数据点
+fn deliver<S: Serialize + Clone>(
+    event: &str,
+    point: Option<Point>,
+    payload: S,
+) -> tauri::Result<()> {
+    let app = get_app();
+    let excluded = match point {
+        Some(point) => excluded_windows(&point),
+        None => HashSet::new(),
+    };
+    if excluded.is_empty() {
+        return app.emit(event, payload);
+    }
+    app.emit_filter(
+        event,
+        payload,
+        |target| !matches!(target_label(target), Some(label) if excluded.contains(label)),
+    )
+}
+
+// 设置批量推送模式，关闭时先推送已暂存的变化
+pub fn set_batch_mode(enabled: bool, window_ms: u64) {
+    {
+        let mut batch = BATCH.lock().unwrap();
+        batch.enabled = enabled;
+        batch.window_ms = window_ms;
+    }
+    if !enabled {
+        flush_batch();
+    }
+}
+
+fn is_batching() -> bool {
+    BATCH.lock().unwrap().enabled
+}
+
+fn queue_update(update: ValueUpdate) {
+    let mut batch = BATCH.lock().unwrap();
+    if batch.pending.is_empty() {
+        batch.window_start = Some(Instant::now());
+    }
+    batch.pending.push(update);
+}
+
+// 调度器每个周期结束时调用，达到推送窗口后一次性推送所有暂存的变化
+pub fn flush_batch() {
+    let changes = {
+        let mut batch = BATCH.lock().unwrap();
+        if batch.pending.is_empty() {
+            return;
+        }
+        let window_elapsed = match batch.window_start {
+            Some(start) => start.elapsed() >= Duration::from_millis(batch.window_ms),
+            None => true,
+        };
+        if batch.enabled && !window_elapsed {
+            return;
+        }
+        batch.window_start = None;
+        std::mem::take(&mut batch.pending)
+    };
+
+    #[cfg(debug_assertions)]
+    println!("发送批量更新 - Changes: {}", changes.len());
+
+    let app = get_app();
+
+    // 有订阅的窗口只接收筛选后的变化，其余窗口接收全部变化
+    let targets: Vec<(String, Vec<ValueUpdate>)> = {
+        let subscriptions = SUBSCRIPTIONS.lock().unwrap();
+        subscriptions
+            .iter()
+            .map(|(label, subscription)| {
+                let changes = changes
+                    .iter()
+                    .filter(|update| subscription.matches(&update.point()))
+                    .cloned()
+                    .collect();
+                (label.clone(), changes)
+            })
+            .collect()
+    };
+    let subscribed: HashSet<String> = targets.iter().map(|(label, _)| label.clone()).collect();
+
+    let result =
+        targets
+            .into_iter()
+            .filter(|(_, changes)| !changes.is_empty())
+            .try_for_each(|(label, changes)| {
+                app.emit_to(label.as_str(), "plc-batch-update", BatchUpdate { changes })
+            })
+            .and_then(|()| {
+                app.emit_filter("plc-batch-update", BatchUpdate { changes }, |target| {
+                !matches!(target_label(target), Some(label) if subscribed.contains(label))
+            })
+            });
+
+    if let Err(e) = result {
+        eprintln!("Failed to emit batch update: {}", e);
+    }
+}
+
 #[tauri::command]
-pub fn notify_bool(client_id: i64, address: u16, value: bool) {
+pub fn notify_bool(
+    client_id: i64,
+    address: u16,
+    value: bool,
+    quality: Quality,
+    timestamp: u64,
+    latency_ms: f64,
+) {
     #[cfg(debug_assertions)]
     println!(
         "发送布尔值更新 - Client Id: {}, Address: {}, Value: {}",
         client_id, address, value
     );
 
-    let app = get_app();
+    let payload = BoolValue {
+        client_id,
+        address,
+        value,
+        quality,
+        timestamp,
+        latency_ms,
+    };
 
-    if let Err(e) = app.emit(
+    // 批量模式下先暂存，由调度器在周期结束时统一推送
+    if is_batching() {
+        queue_update(ValueUpdate::Bool(payload));
+        return;
+    }
+
+    if let Err(e) = deliver(
         "plc-bool-update",
-        BoolValue {
-            client_id,
-            address,
-            value,
-        },
+        Some((client_id, RegisterTable::Coil, address)),
+        payload,
     ) {
         eprintln!("Failed to emit bool value: {}", e);
     }
 }
 
 #[tauri::command]
-pub fn notify_word(client_id: i64, address: u16, read_only: bool, value: u16) {
+pub fn notify_word(
+    client_id: i64,
+    address: u16,
+    read_only: bool,
+    value: u16,
+    quality: Quality,
+    timestamp: u64,
+    latency_ms: f64,
+) {
     #[cfg(debug_assertions)]
     println!(
         "发送字值更新 - Client Id: {}, Address: {}, Value: {}",
         client_id, address, value
     );
 
-    let app = get_app();
+    let payload = WordValue {
+        client_id,
+        address,
+        read_only,
+        value,
+        quality,
+        timestamp,
+        latency_ms,
+    };
 
-    if let Err(e) = app.emit(
+    if is_batching() {
+        queue_update(ValueUpdate::Word(payload));
+        return;
+    }
+
+    if let Err(e) = deliver(
         "plc-word-update",
-        WordValue {
-            client_id,
-            address,
-            read_only,
-            value,
-        },
+        Some((client_id, register_table(read_only), address)),
+        payload,
     ) {
         eprintln!("Failed to emit word value: {}", e);
     }
 }
 
 #[tauri::command]
-pub fn notify_dword(client_id: i64, address: u16, read_only: bool, value: u32) {
+pub fn notify_dword(
+    client_id: i64,
+    address: u16,
+    read_only: bool,
+    value: u32,
+    quality: Quality,
+    timestamp: u64,
+    latency_ms: f64,
+) {
     #[cfg(debug_assertions)]
     println!(
         "发送双字值更新 - Client Id: {}, Address: {}, Value: {}",
         client_id, address, value
     );
 
-    let app = get_app();
+    let payload = DwordValue {
+        client_id,
+        address,
+        read_only,
+        value,
+        quality,
+        timestamp,
+        latency_ms,
+    };
 
-    if let Err(e) = app.emit(
+    if is_batching() {
+        queue_update(ValueUpdate::Dword(payload));
+        return;
+    }
+
+    if let Err(e) = deliver(
         "plc-dword-update",
-        DwordValue {
-            client_id,
-            address,
-            read_only,
-            value,
-        },
+        Some((client_id, register_table(read_only), address)),
+        payload,
     ) {
         eprintln!("Failed to emit dword value: {}", e);
     }
 }
 
 #[tauri::command]
-pub fn notify_float(client_id: i64, address: u16, read_only: bool, value: f32) {
+pub fn notify_float(
+    client_id: i64,
+    address: u16,
+    read_only: bool,
+    value: f32,
+    quality: Quality,
+    timestamp: u64,
+    latency_ms: f64,
+) {
     #[cfg(debug_assertions)]
     println!(
         "发送浮点值更新 - Client Id: {}, Address: {}, Value: {}",
         client_id, address, value
     );
 
+    let payload = FloatValue {
+        client_id,
+        address,
+        read_only,
+        value,
+        quality,
+        timestamp,
+        latency_ms,
+    };
+
+    if is_batching() {
+        queue_update(ValueUpdate::Float(payload));
+        return;
+    }
+
+    if let Err(e) = deliver(
+        "plc-float-update",
+        Some((client_id, register_table(read_only), address)),
+        payload,
+    ) {
+        eprintln!("Failed to emit float value: {}", e);
+    }
+}
+
+#[tauri::command]
+pub fn notify_task_error(
+    client_id: i64,
+    address: u16,
+    data_type: u8,
+    read_only: bool,
+    quality: Quality,
+    error: String,
+    timestamp: u64,
+) {
+    #[cfg(debug_assertions)]
+    println!(
+        "发送任务错误 - Client Id: {}, Address: {}, Quality: {:?}, Error: {}",
+        client_id, address, quality, error
+    );
+
+    let payload = TaskError {
+        client_id,
+        address,
+        data_type,
+        read_only,
+        quality,
+        error,
+        timestamp,
+    };
+
+    let point = (
+        client_id,
+        RegisterTable::of(DataType::from(data_type), read_only),
+        address,
+    );
+    if let Err(e) = deliver("plc-task-error", Some(point), payload) {
+        eprintln!("Failed to emit task error: {}", e);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub fn notify_trigger_read(
+    id: u64,
+    client_id: i64,
+    address: u16,
+    data_type: u8,
+    read_only: bool,
+    values: Vec<PlcValue>,
+    quality: Quality,
+    error: Option<String>,
+    timestamp: u64,
+) {
+    #[cfg(debug_assertions)]
+    println!(
+        "发送触发读取结果 - ID: {}, Client Id: {}, Address: {}, Values: {}",
+        id,
+        client_id,
+        address,
+        values.len()
+    );
+
+    let payload = TriggerRead {
+        id: id.to_string(),
+        client_id,
+        address,
+        data_type,
+        read_only,
+        values,
+        quality,
+        error,
+        timestamp,
+    };
+
+    let point = (
+        client_id,
+        RegisterTable::of(DataType::from(data_type), read_only),
+        address,
+    );
+    if let Err(e) = deliver("plc-trigger-read", Some(point), payload) {
+        eprintln!("Failed to emit trigger read: {}", e);
+    }
+}
+
+#[tauri::command]
+pub fn notify_alarm(event: AlarmEvent) {
+    #[cfg(debug_assertions)]
+    println!(
+        "发送报警事件 - Id: {}, Tag: {}, State: {:?}",
+        event.id, event.tag, event.state
+    );
+
+    let app = get_app();
+
+    if let Err(e) = app.emit("plc-alarm", event) {
+        eprintln!("Failed to emit alarm event: {}", e);
+    }
+}
+
+pub fn notify_scheduler_stats(stats: SchedulerStats) {
+    let app = get_app();
+
+    if let Err(e) = app.emit("plc-scheduler-stats", stats) {
+        eprintln!("Failed to emit scheduler stats: {}", e);
+    }
+}
+
+// table 为空时不按订阅过滤，用于没有地址的虚拟标签
+pub fn notify_tag_value(payload: TagValue, table: Option<RegisterTable>) {
+    #[cfg(debug_assertions)]
+    println!(
+        "发送标签更新 - Name: {}, Value: {:?}, Quality: {:?}",
+        payload.name, payload.value, payload.quality
+    );
+
+    let point = table.map(|table| (payload.client_id, table, payload.address));
+    if let Err(e) = deliver("plc-tag-update", point, payload) {
+        eprintln!("Failed to emit tag value: {}", e);
+    }
+}
+
+#[tauri::command]
+pub fn notify_device_state(
+    client_id: i64,
+    state: DeviceState,
+    consecutive_failures: u32,
+    timestamp: u64,
+) {
+    #[cfg(debug_assertions)]
+    println!(
+        "发送设备状态 - Client Id: {}, State: {:?}, Failures: {}",
+        client_id, state, consecutive_failures
+    );
+
     let app = get_app();
 
     if let Err(e) = app.emit(
-        "plc-float-update",
-        FloatValue {
+        "plc-device-state",
+        DeviceStateChange {
             client_id,
-            address,
-            read_only,
-            value,
+            state,
+            consecutive_failures,
+            timestamp,
         },
     ) {
-        eprintln!("Failed to emit float value: {}", e);
+        eprintln!("Failed to emit device state: {}", e);
+    }
+}
+
+pub fn notify_script_output(output: ScriptOutput) {
+    #[cfg(debug_assertions)]
+    println!(
+        "发送脚本输出 - Id: {}, Kind: {:?}, Message: {}",
+        output.id, output.kind, output.message
+    );
+
+    let app = get_app();
+
+    if let Err(e) = app.emit("plc-script-output", output) {
+        eprintln!("Failed to emit script output: {}", e);
+    }
+}
+
+pub fn notify_script_state(info: ScriptInfo) {
+    #[cfg(debug_assertions)]
+    println!(
+        "发送脚本状态 - Id: {}, Name: {}, State: {:?}",
+        info.id, info.name, info.state
+    );
+
+    let app = get_app();
+
+    if let Err(e) = app.emit("plc-script-state", info) {
+        eprintln!("Failed to emit script state: {}", e);
     }
 }
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use crate::plc::{RegisterTable, TaskDefinition};
+
+// Modbus 协议单次读取的数量上限
+pub const MAX_READ_REGISTERS: u16 = 125;
+pub const MAX_READ_COILS: u16 = 2000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct OptimizerConfig {
+    pub enabled: bool,
+    // 允许合并的两个任务之间最多空出的地址数
+    pub max_gap: u16,
+}
+
+impl Default for OptimizerConfig {
+    fn default() -> Self {
+        OptimizerConfig {
+            enabled: true,
+            max_gap: 0,
+        }
+    }
+}
+
+/// 一次块读取，覆盖若干个任务
+#[derive(Debug, Clone)]
+pub struct ReadBlock {
+    pub client_id: i64,
+    pub table: RegisterTable,
+    pub address: u16,
+    pub quantity: u16,
+    pub tasks: Vec<TaskDefinition>,
+}
+
+impl ReadBlock {
+    pub fn single(task: TaskDefinition) -> Self {
+        ReadBlock {
+            client_id: task.client_id,
+            table: task.table(),
+            address: task.address,
+            quantity: task.data_type.width(),
+            tasks: vec![task],
+        }
+    }
+
+    fn end(&self) -> u32 {
+        self.address as u32 + self.quantity as u32
+    }
+
+    // 尝试把任务并入当前块，超出协议上限或间隔过大时返回 false
+    fn try_merge(&mut self, task: &TaskDefinition, max_gap: u16) -> bool {
+        let task_start = task.address as u32;
+        let task_end = task_start + task.data_type.width() as u32;
+        if task_start > self.end() + max_gap as u32 {
+            return false;
+        }
+
+        let new_end = self.end().max(task_end);
+        if new_end - self.address as u32 > max_quantity(self.table) as u32 {
+            return false;
+        }
+
+        self.quantity = (new_end - self.address as u32) as u16;
+        self.tasks.push(task.clone());
+        true
+    }
+
+    /// 拆分为单任务读取，块读取失败时用于逐个重试
+    pub fn split(&self) -> Vec<ReadBlock> {
+        self.tasks.iter().cloned().map(ReadBlock::single).collect()
+    }
+}
+
+fn max_quantity(table: RegisterTable) -> u16 {
+    match table {
+        RegisterTable::Coil => MAX_READ_COILS,
+        RegisterTable::Holding | RegisterTable::Input => MAX_READ_REGISTERS,
+    }
+}
+
+/// 将同一连接、同一寄存器表、同一周期的任务合并为连续的块读取
+pub fn build_blocks(tasks: Vec<TaskDefinition>, config: &OptimizerConfig) -> Vec<ReadBlock> {
+    if !config.enabled {
+        return tasks.into_iter().map(ReadBlock::single).collect();
+    }
+
+    let mut groups: HashMap<(i64, RegisterTable, u64), Vec<TaskDefinition>> = HashMap::new();
+    for task in tasks {
+        groups
+            .entry((task.client_id, task.table(), task.interval_ms))
+            .or_default()
+            .push(task);
+    }
+
+    let mut blocks = Vec::new();
+    for (_, mut group) in groups {
+        group.sort_by_key(|task| task.address);
+
+        let mut current: Option<ReadBlock> = None;
+        for task in group {
+            if let Some(block) = current.as_mut() {
+                if block.try_merge(&task, config.max_gap) {
+                    continue;
+                }
+            }
+            if let Some(block) = current.replace(ReadBlock::single(task)) {
+                blocks.push(block);
+            }
+        }
+        if let Some(block) = current {
+            blocks.push(block);
+        }
+    }
+
+    blocks
+}
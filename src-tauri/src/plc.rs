@@ -1,31 +1,91 @@
 use lazy_static::lazy_static;
-use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 use tokio::time;
 
-use crate::modbus::{ModbusError, MODBUS_MANAGER};
-use crate::notice::{notify_bool, notify_dword, notify_float, notify_word};
+use crate::historian;
+use crate::modbus::{ModbusError, Priority, MODBUS_MANAGER};
+use crate::notice::{
+    flush_batch, notify_bool, notify_device_state, notify_dword, notify_float,
+    notify_scheduler_stats, notify_task_error, notify_trigger_read, notify_word, DeviceState,
+    Quality,
+};
+use crate::optimizer::{
+    build_blocks, OptimizerConfig, ReadBlock, MAX_READ_COILS, MAX_READ_REGISTERS,
+};
+use crate::stats::{ConnectionStats, ConnectionTiming, SchedulerStats, TaskStats, TaskTiming};
+use crate::tags;
 
 #[derive(Error, Debug)]
 pub enum PLCError {
     #[error("任务未找到: 客户端 ID {client_id}, 地址 {address}")]
     TaskNotFound { client_id: i64, address: u16 },
 
+    #[error("标签未找到: {0}")]
+    TagNotFound(String),
+
+    #[error("{0}")]
+    Modbus(ModbusError),
+
+    #[error("写入校验失败: 客户端 ID {client_id}, 地址 {address}, 期望 {expected}, 回读 {actual}")]
+    VerifyFailed {
+        client_id: i64,
+        address: u16,
+        expected: f64,
+        actual: f64,
+    },
+
     #[error("{0}")]
     Other(String),
 }
 
+impl PLCError {
+    // 轮询请求因排队过久被丢弃或本地队列已满，请求未发往设备，不视为通信故障
+    fn is_dropped(&self) -> bool {
+        matches!(
+            self,
+            PLCError::Modbus(ModbusError::RequestDropped | ModbusError::QueueFull(_))
+        )
+    }
+
+    fn quality(&self) -> Quality {
+        match self {
+            PLCError::Modbus(ModbusError::Exception(_)) => Quality::BadException,
+            _ => Quality::BadComm,
+        }
+    }
+}
+
 type Result<T> = std::result::Result<T, PLCError>;
 
-type TaskKey = (i64, bool, bool, u16);
+pub type TaskKey = (i64, bool, bool, u16);
+
+// 默认完整性上报周期，即使数值未变化也会按此周期重新推送
+const DEFAULT_INTEGRITY_INTERVAL_MS: u64 = 10_000;
+
+// 超过 N 个采集周期没有成功更新的任务标记为过期
+const STALE_INTERVAL_FACTOR: u32 = 3;
+// 过期检查的间隔（调度器节拍数）
+const STALE_CHECK_TICKS: u64 = 1000;
+
+// 写入校验失败后重试前的等待时间，给设备留出处理时间
+const VERIFY_RETRY_DELAY_MS: u64 = 100;
+
+// 每个任务在内存中保留最近一段时间的读数，供趋势图回填
+const RECENT_WINDOW_MS: u64 = 10 * 60_000;
+const MAX_RECENT_SAMPLES: usize = 10_000;
+
+// 默认统计事件推送周期
+const DEFAULT_STATS_INTERVAL_MS: u64 = 5_000;
 
 impl From<ModbusError> for PLCError {
     fn from(err: ModbusError) -> Self {
-        PLCError::Other(err.to_string())
+        PLCError::Modbus(err)
     }
 }
 
@@ -34,19 +94,21 @@ lazy_static! {
 }
 
 pub async fn read_bool(client_id: i64, address: u16) -> Result<bool> {
-    let values = MODBUS_MANAGER.read_coils(client_id, address, 1).await?;
+    let values = MODBUS_MANAGER
+        .read_coils(client_id, address, 1, Priority::Read)
+        .await?;
     Ok(values[0])
 }
 
 pub async fn read_word(client_id: i64, address: u16, read_only: bool) -> Result<u16> {
     if read_only {
         let values = MODBUS_MANAGER
-            .read_input_registers(client_id, address, 1)
+            .read_input_registers(client_id, address, 1, Priority::Read)
             .await?;
         Ok(values[0])
     } else {
         let values = MODBUS_MANAGER
-            .read_holding_registers(client_id, address, 1)
+            .read_holding_registers(client_id, address, 1, Priority::Read)
             .await?;
         Ok(values[0])
     }
@@ -55,66 +117,299 @@ pub async fn read_word(client_id: i64, address: u16, read_only: bool) -> Result<
 pub async fn read_dword(client_id: i64, address: u16, read_only: bool) -> Result<u32> {
     let values = if read_only {
         MODBUS_MANAGER
-            .read_input_registers(client_id, address, 2)
+            .read_input_registers(client_id, address, 2, Priority::Read)
             .await?
     } else {
         MODBUS_MANAGER
-            .read_holding_registers(client_id, address, 2)
+            .read_holding_registers(client_id, address, 2, Priority::Read)
             .await?
     };
-    let value = (values[1] as u32) << 16 | (values[0] as u32);
-    Ok(value)
+    Ok(decode_dword(&values))
 }
 
 pub async fn read_float(client_id: i64, address: u16, read_only: bool) -> Result<f32> {
     let values = if read_only {
         MODBUS_MANAGER
-            .read_input_registers(client_id, address, 2)
+            .read_input_registers(client_id, address, 2, Priority::Read)
             .await?
     } else {
         MODBUS_MANAGER
-            .read_holding_registers(client_id, address, 2)
+            .read_holding_registers(client_id, address, 2, Priority::Read)
             .await?
     };
-    let bits = (values[1] as u32) << 16 | (values[0] as u32);
-    let value = f32::from_bits(bits);
-    Ok(value)
+    Ok(f32::from_bits(decode_dword(&values)))
 }
 
-pub async fn write_bool(client_id: i64, address: u16, value: bool) -> Result<()> {
-    MODBUS_MANAGER
-        .write_single_coil(client_id, address, value)
-        .await?;
-    Ok(())
+/// 写入后回读校验的参数
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteVerify {
+    // 回读值与写入值允许的最大偏差，布尔值始终要求相等
+    #[serde(default)]
+    pub tolerance: f64,
+    // 校验失败后重新写入的次数
+    #[serde(default)]
+    pub retries: u32,
 }
 
-pub async fn write_word(client_id: i64, address: u16, value: u16) -> Result<()> {
-    MODBUS_MANAGER
-        .write_single_register(client_id, address, value)
-        .await?;
-    Ok(())
+// 写入并回读校验，不匹配时按设定次数重写
+async fn write_verified<W, R, F>(
+    client_id: i64,
+    address: u16,
+    expected: f64,
+    verify: Option<WriteVerify>,
+    write: impl Fn() -> W,
+    read_back: impl Fn() -> R,
+) -> Result<()>
+where
+    W: std::future::Future<Output = Result<()>>,
+    R: std::future::Future<Output = Result<F>>,
+    F: Into<f64>,
+{
+    let Some(verify) = verify else {
+        return write().await;
+    };
+
+    let mut attempt = 0;
+    loop {
+        write().await?;
+        let actual: f64 = read_back().await?.into();
+        // 按位相同时视为一致，使写入 NaN 也能通过校验
+        if actual.to_bits() == expected.to_bits() || (actual - expected).abs() <= verify.tolerance {
+            return Ok(());
+        }
+        if attempt >= verify.retries {
+            return Err(PLCError::VerifyFailed {
+                client_id,
+                address,
+                expected,
+                actual,
+            });
+        }
+
+        attempt += 1;
+        #[cfg(debug_assertions)]
+        println!(
+            "写入校验不一致，重试 {}/{} - Client ID: {}, Address: {}",
+            attempt, verify.retries, client_id, address
+        );
+        time::sleep(Duration::from_millis(VERIFY_RETRY_DELAY_MS)).await;
+    }
 }
 
-pub async fn write_dword(client_id: i64, address: u16, value: u32) -> Result<()> {
-    let low = (value & 0xFFFF) as u16;
-    let high = ((value >> 16) & 0xFFFF) as u16;
-    MODBUS_MANAGER
-        .write_multiple_registers(client_id, address, &vec![low, high])
-        .await?;
-    Ok(())
+pub async fn write_bool(
+    client_id: i64,
+    address: u16,
+    value: bool,
+    verify: Option<WriteVerify>,
+) -> Result<()> {
+    // 布尔值不使用容差
+    let verify = verify.map(|verify| WriteVerify {
+        tolerance: 0.0,
+        ..verify
+    });
+    write_verified(
+        client_id,
+        address,
+        value as u8 as f64,
+        verify,
+        || async {
+            MODBUS_MANAGER
+                .write_single_coil(client_id, address, value)
+                .await?;
+            Ok(())
+        },
+        || async { Ok(read_bool(client_id, address).await? as u8) },
+    )
+    .await
 }
 
-pub async fn write_float(client_id: i64, address: u16, value: f32) -> Result<()> {
-    let bits = value.to_bits();
-    let low = (bits & 0xFFFF) as u16;
-    let high = ((bits >> 16) & 0xFFFF) as u16;
-    MODBUS_MANAGER
-        .write_multiple_registers(client_id, address, &vec![low, high])
-        .await?;
-    Ok(())
+pub async fn write_word(
+    client_id: i64,
+    address: u16,
+    value: u16,
+    verify: Option<WriteVerify>,
+) -> Result<()> {
+    write_verified(
+        client_id,
+        address,
+        value as f64,
+        verify,
+        || async {
+            MODBUS_MANAGER
+                .write_single_register(client_id, address, value)
+                .await?;
+            Ok(())
+        },
+        || read_word(client_id, address, false),
+    )
+    .await
 }
 
-fn generate_task_key(
+pub async fn write_dword(
+    client_id: i64,
+    address: u16,
+    value: u32,
+    verify: Option<WriteVerify>,
+) -> Result<()> {
+    let registers = encode_dword(value);
+    write_verified(
+        client_id,
+        address,
+        value as f64,
+        verify,
+        || async {
+            MODBUS_MANAGER
+                .write_multiple_registers(client_id, address, &registers)
+                .await?;
+            Ok(())
+        },
+        || read_dword(client_id, address, false),
+    )
+    .await
+}
+
+pub async fn write_float(
+    client_id: i64,
+    address: u16,
+    value: f32,
+    verify: Option<WriteVerify>,
+) -> Result<()> {
+    let registers = encode_dword(value.to_bits());
+    write_verified(
+        client_id,
+        address,
+        value as f64,
+        verify,
+        || async {
+            MODBUS_MANAGER
+                .write_multiple_registers(client_id, address, &registers)
+                .await?;
+            Ok(())
+        },
+        || read_float(client_id, address, false),
+    )
+    .await
+}
+
+// 低字在前
+fn decode_dword(values: &[u16]) -> u32 {
+    (values[1] as u32) << 16 | (values[0] as u32)
+}
+
+pub fn encode_dword(value: u32) -> [u16; 2] {
+    [(value & 0xFFFF) as u16, ((value >> 16) & 0xFFFF) as u16]
+}
+
+pub enum BlockData {
+    Bits(Vec<bool>),
+    Registers(Vec<u16>),
+}
+
+// 按寄存器表读取一段连续地址
+async fn read_block(block: &ReadBlock, priority: Priority) -> Result<BlockData> {
+    let client_id = block.client_id;
+    let data = match block.table {
+        RegisterTable::Coil => BlockData::Bits(
+            MODBUS_MANAGER
+                .read_coils(client_id, block.address, block.quantity, priority)
+                .await?,
+        ),
+        RegisterTable::Holding => BlockData::Registers(
+            MODBUS_MANAGER
+                .read_holding_registers(client_id, block.address, block.quantity, priority)
+                .await?,
+        ),
+        RegisterTable::Input => BlockData::Registers(
+            MODBUS_MANAGER
+                .read_input_registers(client_id, block.address, block.quantity, priority)
+                .await?,
+        ),
+    };
+    Ok(data)
+}
+
+// 响应接收时间（毫秒时间戳）和请求耗时
+#[derive(Debug, Clone, Copy)]
+pub struct ReadTiming {
+    pub started: Instant,
+    pub timestamp: u64,
+    pub latency_ms: f64,
+}
+
+// 当前时间的毫秒时间戳
+pub fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+impl ReadTiming {
+    fn since(started: Instant) -> Self {
+        ReadTiming {
+            started,
+            timestamp: unix_millis(),
+            latency_ms: started.elapsed().as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+async fn timed_read_block(
+    block: &ReadBlock,
+    priority: Priority,
+) -> (Result<BlockData>, ReadTiming) {
+    let started = Instant::now();
+    let result = read_block(block, priority).await;
+    (result, ReadTiming::since(started))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum PlcValue {
+    Bool(bool),
+    Word(u16),
+    Dword(u32),
+    Float(f32),
+}
+
+impl PlcValue {
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            PlcValue::Bool(v) => *v as u8 as f64,
+            PlcValue::Word(v) => *v as f64,
+            PlcValue::Dword(v) => *v as f64,
+            PlcValue::Float(v) => *v as f64,
+        }
+    }
+
+    // 判断新值相对上次上报的值是否需要推送
+    fn changed(&self, last: &PlcValue, deadband: Option<Deadband>) -> bool {
+        match (self, deadband) {
+            (PlcValue::Bool(_), _) | (_, None) => self != last,
+            (_, Some(deadband)) => deadband.exceeded(last.as_f64(), self.as_f64()),
+        }
+    }
+
+    // 从块读取结果中取出任务对应的值，offset 为相对块起始地址的偏移
+    fn decode(data_type: DataType, data: &BlockData, offset: usize) -> Option<PlcValue> {
+        match (data_type, data) {
+            (DataType::Bool, BlockData::Bits(bits)) => bits.get(offset).map(|v| PlcValue::Bool(*v)),
+            (DataType::Word, BlockData::Registers(regs)) => {
+                regs.get(offset).map(|v| PlcValue::Word(*v))
+            }
+            (DataType::Dword, BlockData::Registers(regs)) => regs
+                .get(offset..offset + 2)
+                .map(|v| PlcValue::Dword(decode_dword(v))),
+            (DataType::Float, BlockData::Registers(regs)) => regs
+                .get(offset..offset + 2)
+                .map(|v| PlcValue::Float(f32::from_bits(decode_dword(v)))),
+            _ => None,
+        }
+    }
+}
+
+pub fn generate_task_key(
     client_id: i64,
     address: u16,
     data_type: DataType,
@@ -126,6 +421,20 @@ fn generate_task_key(
     (client_id, true, read_only, address)
 }
 
+// 从间隔索引中移除任务，分组为空时一并移除
+fn remove_from_interval(
+    tasks_by_interval: &mut HashMap<u64, HashSet<TaskKey>>,
+    interval_ms: u64,
+    task_key: &TaskKey,
+) {
+    if let Some(group) = tasks_by_interval.get_mut(&interval_ms) {
+        group.remove(task_key);
+        if group.is_empty() {
+            tasks_by_interval.remove(&interval_ms);
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum DataType {
     Bool = 1,
@@ -146,6 +455,294 @@ impl From<u8> for DataType {
     }
 }
 
+impl DataType {
+    // 占用的寄存器（或线圈）数量
+    pub fn width(&self) -> u16 {
+        match self {
+            DataType::Bool | DataType::Word => 1,
+            DataType::Dword | DataType::Float => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Deadband {
+    Absolute(f64),
+    Percent(f64),
+}
+
+impl Deadband {
+    // 0 表示不使用死区，1 为绝对值死区，2 为百分比死区
+    pub fn from_type(deadband_type: u8, value: f64) -> Option<Deadband> {
+        match deadband_type {
+            1 => Some(Deadband::Absolute(value.abs())),
+            2 => Some(Deadband::Percent(value.abs())),
+            _ => None,
+        }
+    }
+
+    // from_type 的逆运算，返回 (死区类型, 死区值)
+    pub fn to_type(deadband: Option<Deadband>) -> (u8, f64) {
+        match deadband {
+            None => (0, 0.0),
+            Some(Deadband::Absolute(value)) => (1, value),
+            Some(Deadband::Percent(value)) => (2, value),
+        }
+    }
+
+    fn exceeded(&self, last: f64, value: f64) -> bool {
+        // NaN 与任何值比较都不成立，只要有一方是 NaN 就视为变化
+        if last.is_nan() || value.is_nan() {
+            return last.is_nan() != value.is_nan();
+        }
+        let delta = (value - last).abs();
+        match self {
+            Deadband::Absolute(band) => delta > *band,
+            Deadband::Percent(percent) => delta > last.abs() * percent / 100.0,
+        }
+    }
+}
+
+// 设备连续通信失败后的退避配置
+#[derive(Debug, Clone, Copy)]
+pub struct BackoffConfig {
+    // 连续失败次数达到该值后进入退避，为零时不退避
+    pub failure_threshold: u32,
+    // 退避期间的探测周期
+    pub probe_interval_ms: u64,
+}
+
+impl Default for BackoffConfig {
+    fn default() -> Self {
+        BackoffConfig {
+            failure_threshold: 3,
+            probe_interval_ms: 5_000,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct DeviceHealth {
+    consecutive_failures: u32,
+    backoff: bool,
+    last_probe: Option<Instant>,
+}
+
+// 连接断开时对其任务的处理方式：挂起时任务、标签和历史记录都保留，重连后继续；
+// 移除时连同该连接的标签及其报警、历史记录一起移除
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisconnectPolicy {
+    Suspend = 1,
+    Remove = 2,
+}
+
+impl From<u8> for DisconnectPolicy {
+    fn from(value: u8) -> Self {
+        match value {
+            2 => DisconnectPolicy::Remove,
+            _ => DisconnectPolicy::Suspend, // 默认挂起
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegisterTable {
+    Coil,
+    Holding,
+    Input,
+}
+
+impl RegisterTable {
+    pub fn of(data_type: DataType, read_only: bool) -> Self {
+        match (data_type, read_only) {
+            (DataType::Bool, _) => RegisterTable::Coil,
+            (_, true) => RegisterTable::Input,
+            (_, false) => RegisterTable::Holding,
+        }
+    }
+}
+
+// 地址引用中的数据区，离散输入只用于识别后给出明确的错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressArea {
+    Coil,
+    DiscreteInput,
+    Input,
+    Holding,
+}
+
+/// 解析后的地址：数据区和从零开始的偏移
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolvedAddress {
+    pub area: AddressArea,
+    pub offset: u16,
+}
+
+// Modicon 编号从 1 开始，5 位编号最大 9999，6 位编号最大 65536
+fn parse_modicon(text: &str) -> Option<ResolvedAddress> {
+    if !(text.len() == 5 || text.len() == 6) || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let area = match &text[..1] {
+        "0" => AddressArea::Coil,
+        "1" => AddressArea::DiscreteInput,
+        "3" => AddressArea::Input,
+        "4" => AddressArea::Holding,
+        _ => return None,
+    };
+    let number: u32 = text[1..].parse().ok()?;
+    if number == 0 || number > u16::MAX as u32 + 1 {
+        return None;
+    }
+    Some(ResolvedAddress {
+        area,
+        offset: (number - 1) as u16,
+    })
+}
+
+// IEC 风格（%MW100、%IW5、%QX3、%IX7）和前缀风格（HR100、IR5、CO3、DI7），偏移从零开始
+fn parse_prefixed(text: &str) -> Option<ResolvedAddress> {
+    let upper = text.to_uppercase();
+    let split = upper.find(|c: char| c.is_ascii_digit())?;
+    let (prefix, number) = upper.split_at(split);
+    let area = match prefix {
+        "HR" | "%MW" | "%MD" | "%MF" => AddressArea::Holding,
+        "IR" | "%IW" | "%ID" => AddressArea::Input,
+        "CO" | "%Q" | "%QX" | "%M" | "%MX" => AddressArea::Coil,
+        "DI" | "%I" | "%IX" => AddressArea::DiscreteInput,
+        _ => return None,
+    };
+    Some(ResolvedAddress {
+        area,
+        offset: number.parse().ok()?,
+    })
+}
+
+/// 解析 Modicon 5/6 位编号、IEC 风格或 HR/IR/CO/DI 前缀的地址引用
+pub fn parse_address(text: &str) -> Result<ResolvedAddress> {
+    let text = text.trim();
+    let resolved = parse_modicon(text)
+        .or_else(|| parse_prefixed(text))
+        .ok_or_else(|| PLCError::Other(format!("无法解析的地址: {}", text)))?;
+    if resolved.area == AddressArea::DiscreteInput {
+        return Err(PLCError::Other(format!("暂不支持离散输入地址: {}", text)));
+    }
+    Ok(resolved)
+}
+
+/// 命令中的地址参数：数字始终是原始偏移，字符串按地址引用解析
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AddressRef {
+    Offset(u16),
+    Reference(String),
+}
+
+impl std::fmt::Display for AddressRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddressRef::Offset(offset) => write!(f, "{}", offset),
+            AddressRef::Reference(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+impl AddressRef {
+    /// 解析为 (偏移, 是否只读)，地址引用中的数据区优先于传入的 read_only
+    pub fn resolve(&self, data_type: DataType, read_only: bool) -> Result<(u16, bool)> {
+        let resolved = match self {
+            AddressRef::Offset(offset) => return Ok((*offset, read_only)),
+            AddressRef::Reference(text) => parse_address(text)?,
+        };
+        match (resolved.area, data_type) {
+            (AddressArea::Coil, DataType::Bool) => Ok((resolved.offset, false)),
+            (AddressArea::Holding, data_type) if data_type != DataType::Bool => {
+                Ok((resolved.offset, false))
+            }
+            (AddressArea::Input, data_type) if data_type != DataType::Bool => {
+                Ok((resolved.offset, true))
+            }
+            _ => Err(PLCError::Other(format!("地址 {} 与数据类型不匹配", self))),
+        }
+    }
+
+    /// 解析写入地址，只读区域不允许写入
+    pub fn resolve_writable(&self, data_type: DataType) -> Result<u16> {
+        match self.resolve(data_type, false)? {
+            (_, true) => Err(PLCError::Other(format!("地址 {} 不可写入", self))),
+            (offset, false) => Ok(offset),
+        }
+    }
+}
+
+// 触发读取的边沿条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerEdge {
+    Rising = 1,
+    Falling = 2,
+    Change = 3,
+}
+
+impl From<u8> for TriggerEdge {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => TriggerEdge::Rising,
+            2 => TriggerEdge::Falling,
+            _ => TriggerEdge::Change, // 默认任意变化
+        }
+    }
+}
+
+impl TriggerEdge {
+    // 数值非零视为真
+    fn matches(&self, last: &PlcValue, value: &PlcValue) -> bool {
+        let was_set = last.as_f64() != 0.0;
+        let is_set = value.as_f64() != 0.0;
+        match self {
+            TriggerEdge::Rising => !was_set && is_set,
+            TriggerEdge::Falling => was_set && !is_set,
+            TriggerEdge::Change => last != value,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum TriggerCondition {
+    // 延时到期后执行一次
+    Delay { fire_at: Instant },
+    // 引用的任务数值出现指定边沿时执行
+    OnChange { source: TaskKey, edge: TriggerEdge },
+}
+
+#[derive(Debug, Clone)]
+pub struct TriggerDefinition {
+    pub id: u64,
+    pub client_id: i64,
+    pub address: u16,
+    pub data_type: DataType,
+    pub read_only: bool,
+    // 读取的数据个数，按数据类型计
+    pub quantity: u16,
+    condition: TriggerCondition,
+}
+
+impl TriggerDefinition {
+    // 是否为以这些任务之一为源的边沿触发
+    fn watches(&self, task_keys: &[TaskKey]) -> bool {
+        matches!(&self.condition, TriggerCondition::OnChange { source, .. } if task_keys.contains(source))
+    }
+
+    fn block(&self) -> ReadBlock {
+        ReadBlock {
+            client_id: self.client_id,
+            table: RegisterTable::of(self.data_type, self.read_only),
+            address: self.address,
+            quantity: self.quantity * self.data_type.width(),
+            tasks: Vec::new(),
+        }
+    }
+}
+
 pub struct TaskScheduler {
     tasks: Arc<Mutex<HashMap<TaskKey, TaskDefinition>>>,
     tasks_by_interval: Arc<Mutex<HashMap<u64, HashSet<TaskKey>>>>,
@@ -153,15 +750,69 @@ pub struct TaskScheduler {
     counter: Arc<Mutex<u64>>,
     running: Arc<Mutex<bool>>,
     execution_lock: Arc<Mutex<()>>,
+    read_optimizer: Arc<Mutex<OptimizerConfig>>,
+    task_states: Arc<Mutex<HashMap<TaskKey, TaskState>>>,
+    integrity_interval_ms: Arc<Mutex<u64>>,
+    disconnect_policy: Arc<Mutex<DisconnectPolicy>>,
+    triggers: Arc<Mutex<HashMap<u64, TriggerDefinition>>>,
+    pending_triggers: Arc<Mutex<Vec<u64>>>,
+    next_trigger_id: Arc<Mutex<u64>>,
+    connection_stats: Arc<Mutex<HashMap<i64, ConnectionTiming>>>,
+    stats_interval_ms: Arc<Mutex<u64>>,
+    backoff_config: Arc<Mutex<BackoffConfig>>,
+    device_health: Arc<Mutex<HashMap<i64, DeviceHealth>>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TaskDefinition {
     pub client_id: i64,
     pub address: u16,
     pub data_type: DataType,
     pub read_only: bool,
     pub interval_ms: u64,
+    pub deadband: Option<Deadband>,
+    pub paused: bool,
+    // 连接断开时挂起，重新连接后自动恢复
+    pub suspended: bool,
+}
+
+// 任务运行时状态，用于变化检测和质量跟踪
+#[derive(Debug, Default)]
+struct TaskState {
+    // 最近一次读取到的值，不受死区过滤影响，用于触发条件判断
+    last_read: Option<PlcValue>,
+    last_value: Option<PlcValue>,
+    last_emit: Option<Instant>,
+    last_update: Option<Instant>,
+    quality: Quality,
+    last_error: Option<String>,
+    timing: TaskTiming,
+    // 最近的读数，不受死区过滤影响
+    recent: VecDeque<(u64, PlcValue)>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStatus {
+    pub quality: Quality,
+    pub last_error: Option<String>,
+    pub interval_ms: u64,
+    pub paused: bool,
+    pub suspended: bool,
+}
+
+impl TaskDefinition {
+    fn is_active(&self) -> bool {
+        !self.paused && !self.suspended
+    }
+
+    pub fn key(&self) -> TaskKey {
+        generate_task_key(self.client_id, self.address, self.data_type, self.read_only)
+    }
+
+    pub fn table(&self) -> RegisterTable {
+        RegisterTable::of(self.data_type, self.read_only)
+    }
 }
 
 impl TaskScheduler {
@@ -173,7 +824,286 @@ impl TaskScheduler {
             counter: Arc::new(Mutex::new(0)),
             running: Arc::new(Mutex::new(false)),
             execution_lock: Arc::new(Mutex::new(())),
+            read_optimizer: Arc::new(Mutex::new(OptimizerConfig::default())),
+            task_states: Arc::new(Mutex::new(HashMap::new())),
+            integrity_interval_ms: Arc::new(Mutex::new(DEFAULT_INTEGRITY_INTERVAL_MS)),
+            disconnect_policy: Arc::new(Mutex::new(DisconnectPolicy::Suspend)),
+            triggers: Arc::new(Mutex::new(HashMap::new())),
+            pending_triggers: Arc::new(Mutex::new(Vec::new())),
+            next_trigger_id: Arc::new(Mutex::new(0)),
+            connection_stats: Arc::new(Mutex::new(HashMap::new())),
+            stats_interval_ms: Arc::new(Mutex::new(DEFAULT_STATS_INTERVAL_MS)),
+            backoff_config: Arc::new(Mutex::new(BackoffConfig::default())),
+            device_health: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub async fn set_backoff(&self, failure_threshold: u32, probe_interval_ms: u64) -> Result<()> {
+        if probe_interval_ms == 0 {
+            return Err(PLCError::Other("探测周期不能为零".to_string()));
         }
+        *self.backoff_config.lock().await = BackoffConfig {
+            failure_threshold,
+            probe_interval_ms,
+        };
+        Ok(())
+    }
+
+    // 注册一次性延时读取，返回读取 ID
+    pub async fn schedule_read(
+        &self,
+        client_id: i64,
+        address: u16,
+        data_type: u8,
+        read_only: bool,
+        quantity: u16,
+        delay_ms: u64,
+    ) -> Result<u64> {
+        let condition = TriggerCondition::Delay {
+            fire_at: Instant::now() + Duration::from_millis(delay_ms),
+        };
+        self.add_trigger(
+            client_id, address, data_type, read_only, quantity, condition,
+        )
+        .await
+    }
+
+    // 注册触发读取，当引用的任务数值出现指定边沿时执行，返回触发 ID
+    #[allow(clippy::too_many_arguments)]
+    pub async fn register_trigger(
+        &self,
+        client_id: i64,
+        address: u16,
+        data_type: u8,
+        read_only: bool,
+        quantity: u16,
+        source_client_id: i64,
+        source_address: u16,
+        source_data_type: u8,
+        source_read_only: bool,
+        edge: TriggerEdge,
+    ) -> Result<u64> {
+        let source = generate_task_key(
+            source_client_id,
+            source_address,
+            DataType::from(source_data_type),
+            source_read_only,
+        );
+        if !self.tasks.lock().await.contains_key(&source) {
+            return Err(PLCError::TaskNotFound {
+                client_id: source_client_id,
+                address: source_address,
+            });
+        }
+
+        let condition = TriggerCondition::OnChange { source, edge };
+        self.add_trigger(
+            client_id, address, data_type, read_only, quantity, condition,
+        )
+        .await
+    }
+
+    async fn add_trigger(
+        &self,
+        client_id: i64,
+        address: u16,
+        data_type: u8,
+        read_only: bool,
+        quantity: u16,
+        condition: TriggerCondition,
+    ) -> Result<u64> {
+        let data_type = DataType::from(data_type);
+        let max_quantity = match data_type {
+            DataType::Bool => MAX_READ_COILS,
+            _ => MAX_READ_REGISTERS / data_type.width(),
+        };
+        if quantity == 0 || quantity > max_quantity {
+            return Err(PLCError::Other(format!(
+                "读取数量必须在 1 到 {} 之间",
+                max_quantity
+            )));
+        }
+
+        let id = {
+            let mut next_trigger_id = self.next_trigger_id.lock().await;
+            *next_trigger_id += 1;
+            *next_trigger_id
+        };
+        let trigger = TriggerDefinition {
+            id,
+            client_id,
+            address,
+            data_type,
+            read_only,
+            quantity,
+            condition,
+        };
+        self.triggers.lock().await.insert(id, trigger);
+        Ok(id)
+    }
+
+    pub async fn unregister_trigger(&self, id: u64) -> Result<()> {
+        self.triggers
+            .lock()
+            .await
+            .remove(&id)
+            .map(|_| ())
+            .ok_or(PLCError::Other(format!("触发读取未找到: {}", id)))
+    }
+
+    pub async fn set_disconnect_policy(&self, policy: DisconnectPolicy) -> Result<()> {
+        *self.disconnect_policy.lock().await = policy;
+        Ok(())
+    }
+
+    // 连接断开后按策略移除或挂起该连接的任务，返回受影响的任务数量
+    pub async fn release_client_tasks(&self, client_id: i64) -> Result<usize> {
+        self.device_health.lock().await.remove(&client_id);
+        let policy = *self.disconnect_policy.lock().await;
+        let mut tasks = self.tasks.lock().await;
+        let task_keys: Vec<TaskKey> = tasks
+            .iter()
+            .filter(|(_, task)| task.client_id == client_id)
+            .map(|(task_key, _)| *task_key)
+            .collect();
+
+        match policy {
+            DisconnectPolicy::Remove => {
+                let mut tasks_by_interval = self.tasks_by_interval.lock().await;
+                let mut task_states = self.task_states.lock().await;
+                for task_key in &task_keys {
+                    if let Some(task) = tasks.remove(task_key) {
+                        remove_from_interval(&mut tasks_by_interval, task.interval_ms, task_key);
+                    }
+                    task_states.remove(task_key);
+                }
+                // 同时移除以该连接任务为源的边沿触发，避免留下永远不会执行的触发
+                self.triggers.lock().await.retain(|_, trigger| {
+                    trigger.client_id != client_id && !trigger.watches(&task_keys)
+                });
+                drop(task_states);
+                drop(tasks_by_interval);
+                drop(tasks);
+
+                // 标签和历史记录指向的任务已不存在，释放调度器的锁后一并移除
+                tags::remove_client_tags(client_id).await;
+                historian::disable_client(client_id).await;
+            }
+            DisconnectPolicy::Suspend => {
+                let mut suspended = Vec::new();
+                for task_key in &task_keys {
+                    if let Some(task) = tasks.get_mut(task_key) {
+                        task.suspended = true;
+                        suspended.push(task.clone());
+                    }
+                }
+                drop(tasks);
+
+                let timestamp = unix_millis();
+                for task in &suspended {
+                    let error = "连接已断开".to_string();
+                    Self::report_error(task, Quality::Stale, error, timestamp).await;
+                }
+            }
+        }
+
+        Ok(task_keys.len())
+    }
+
+    // 同一连接重新建立后恢复被挂起的任务，返回恢复的任务数量
+    pub async fn restore_client_tasks(&self, client_id: i64) -> Result<usize> {
+        let mut tasks = self.tasks.lock().await;
+        let task_keys: Vec<TaskKey> = tasks
+            .iter_mut()
+            .filter(|(_, task)| task.client_id == client_id && task.suspended)
+            .map(|(task_key, task)| {
+                task.suspended = false;
+                *task_key
+            })
+            .collect();
+        drop(tasks);
+
+        // 重新连接后从正常状态开始
+        self.device_health.lock().await.remove(&client_id);
+        self.reset_last_update(&task_keys).await;
+        Ok(task_keys.len())
+    }
+
+    // 设置完整性上报周期，为零时只在数值变化时推送
+    pub async fn set_integrity_interval(&self, interval_ms: u64) -> Result<()> {
+        let mut integrity_interval_ms = self.integrity_interval_ms.lock().await;
+        *integrity_interval_ms = interval_ms;
+        Ok(())
+    }
+
+    // 任务在 since 之后的最近读数
+    pub async fn recent_values(&self, task_key: &TaskKey, since: u64) -> Vec<(u64, PlcValue)> {
+        match self.task_states.lock().await.get(task_key) {
+            Some(state) => state
+                .recent
+                .iter()
+                .filter(|(timestamp, _)| *timestamp > since)
+                .copied()
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // 所有任务定义的快照
+    pub async fn task_definitions(&self) -> Vec<TaskDefinition> {
+        self.tasks.lock().await.values().cloned().collect()
+    }
+
+    pub async fn set_task_deadband(
+        &self,
+        client_id: i64,
+        address: u16,
+        data_type: u8,
+        read_only: bool,
+        deadband: Option<Deadband>,
+    ) -> Result<()> {
+        let data_type = DataType::from(data_type);
+        let task_key = generate_task_key(client_id, address, data_type, read_only);
+        let mut tasks = self.tasks.lock().await;
+        let task = tasks
+            .get_mut(&task_key)
+            .ok_or(PLCError::TaskNotFound { client_id, address })?;
+        task.deadband = deadband;
+        Ok(())
+    }
+
+    pub async fn set_read_optimizer(&self, enabled: bool, max_gap: u16) -> Result<()> {
+        let mut config = self.read_optimizer.lock().await;
+        *config = OptimizerConfig { enabled, max_gap };
+        Ok(())
+    }
+
+    pub async fn get_task_status(
+        &self,
+        client_id: i64,
+        address: u16,
+        data_type: u8,
+        read_only: bool,
+    ) -> Result<TaskStatus> {
+        let data_type = DataType::from(data_type);
+        let task_key = generate_task_key(client_id, address, data_type, read_only);
+        let (interval_ms, paused, suspended) = match self.tasks.lock().await.get(&task_key) {
+            Some(task) => (task.interval_ms, task.paused, task.suspended),
+            None => return Err(PLCError::TaskNotFound { client_id, address }),
+        };
+
+        let task_states = self.task_states.lock().await;
+        let (quality, last_error) = match task_states.get(&task_key) {
+            Some(state) => (state.quality, state.last_error.clone()),
+            None => (Quality::default(), None),
+        };
+        Ok(TaskStatus {
+            quality,
+            last_error,
+            interval_ms,
+            paused,
+            suspended,
+        })
     }
 
     pub async fn register_task(
@@ -191,20 +1121,29 @@ impl TaskScheduler {
 
         let data_type = DataType::from(data_type);
         let task_key = generate_task_key(client_id, address, data_type, read_only);
+
+        // 添加任务到任务列表，重复注册时保留原有死区设置
+        let mut tasks = self.tasks.lock().await;
         let task = TaskDefinition {
             client_id,
             address,
             data_type,
             read_only,
             interval_ms,
+            deadband: tasks.get(&task_key).and_then(|task| task.deadband),
+            paused: false,
+            suspended: false,
         };
+        let previous = tasks.insert(task_key, task);
 
-        // 添加任务到任务列表
-        let mut tasks = self.tasks.lock().await;
-        tasks.insert(task_key, task);
+        // 重置变化检测状态，确保新注册的任务立即推送一次
+        self.task_states.lock().await.remove(&task_key);
 
-        // 添加任务到间隔索引
+        // 添加任务到间隔索引，重复注册时先从原间隔分组中移除
         let mut tasks_by_interval = self.tasks_by_interval.lock().await;
+        if let Some(previous) = previous {
+            remove_from_interval(&mut tasks_by_interval, previous.interval_ms, &task_key);
+        }
         let group = tasks_by_interval
             .entry(interval_ms)
             .or_insert_with(HashSet::new);
@@ -226,18 +1165,99 @@ impl TaskScheduler {
 
         if let Some(task) = tasks.remove(&task_key) {
             let mut tasks_by_interval = self.tasks_by_interval.lock().await;
-            if let Some(group) = tasks_by_interval.get_mut(&task.interval_ms) {
-                group.remove(&task_key);
-                if group.is_empty() {
-                    tasks_by_interval.remove(&task.interval_ms);
-                }
-            }
+            remove_from_interval(&mut tasks_by_interval, task.interval_ms, &task_key);
+            self.task_states.lock().await.remove(&task_key);
+            self.triggers
+                .lock()
+                .await
+                .retain(|_, trigger| !trigger.watches(&[task_key]));
             Ok(())
         } else {
             Err(PLCError::TaskNotFound { client_id, address })
         }
     }
 
+    // 原子地修改任务的采集周期
+    pub async fn set_task_interval(
+        &self,
+        client_id: i64,
+        address: u16,
+        data_type: u8,
+        read_only: bool,
+        interval_ms: u64,
+    ) -> Result<()> {
+        if interval_ms == 0 {
+            return Err(PLCError::Other("间隔时间不能为零".to_string()));
+        }
+
+        let data_type = DataType::from(data_type);
+        let task_key = generate_task_key(client_id, address, data_type, read_only);
+        let mut tasks = self.tasks.lock().await;
+        let task = tasks
+            .get_mut(&task_key)
+            .ok_or(PLCError::TaskNotFound { client_id, address })?;
+
+        let mut tasks_by_interval = self.tasks_by_interval.lock().await;
+        remove_from_interval(&mut tasks_by_interval, task.interval_ms, &task_key);
+        tasks_by_interval
+            .entry(interval_ms)
+            .or_insert_with(HashSet::new)
+            .insert(task_key);
+        task.interval_ms = interval_ms;
+
+        Ok(())
+    }
+
+    pub async fn set_task_paused(
+        &self,
+        client_id: i64,
+        address: u16,
+        data_type: u8,
+        read_only: bool,
+        paused: bool,
+    ) -> Result<()> {
+        let data_type = DataType::from(data_type);
+        let task_key = generate_task_key(client_id, address, data_type, read_only);
+        let mut tasks = self.tasks.lock().await;
+        let task = tasks
+            .get_mut(&task_key)
+            .ok_or(PLCError::TaskNotFound { client_id, address })?;
+        task.paused = paused;
+
+        if !paused {
+            self.reset_last_update(&[task_key]).await;
+        }
+        Ok(())
+    }
+
+    // 暂停或恢复指定连接的所有任务，返回受影响的任务数量
+    pub async fn set_client_paused(&self, client_id: i64, paused: bool) -> Result<usize> {
+        let mut tasks = self.tasks.lock().await;
+        let task_keys: Vec<TaskKey> = tasks
+            .iter_mut()
+            .filter(|(_, task)| task.client_id == client_id && task.paused != paused)
+            .map(|(task_key, task)| {
+                task.paused = paused;
+                *task_key
+            })
+            .collect();
+
+        if !paused {
+            self.reset_last_update(&task_keys).await;
+        }
+        Ok(task_keys.len())
+    }
+
+    // 恢复采集后重新计算过期时间，避免在首次采集前被误判为过期
+    async fn reset_last_update(&self, task_keys: &[TaskKey]) {
+        let mut task_states = self.task_states.lock().await;
+        for task_key in task_keys {
+            if let Some(state) = task_states.get_mut(task_key) {
+                state.last_update = None;
+            }
+        }
+    }
+
     pub async fn start(&self) -> Result<()> {
         let mut running = self.running.lock().await;
         if *running {
@@ -248,6 +1268,8 @@ impl TaskScheduler {
         let tasks_by_interval = self.tasks_by_interval.clone();
         let counter = self.counter.clone();
         let running_clone = self.running.clone();
+        let read_optimizer = self.read_optimizer.clone();
+        let stats_interval_ms = self.stats_interval_ms.clone();
 
         let handle = tokio::spawn(async move {
             let mut interval = time::interval(Duration::from_millis(1));
@@ -283,19 +1305,71 @@ impl TaskScheduler {
                     result
                 };
 
-                // 执行任务
-                let tasks = tasks.lock().await;
-                for task_id in tasks_to_execute {
-                    if let Some(task) = tasks.get(&task_id) {
+                // 处于退避状态的设备暂停正常轮询，改为定期探测
+                let backoff_clients = Self::backoff_clients().await;
+
+                // 取出任务定义后立即释放锁，避免读取期间阻塞任务注册
+                let due_tasks: Vec<TaskDefinition> = {
+                    let tasks = tasks.lock().await;
+                    tasks_to_execute
+                        .iter()
+                        .filter_map(|task_id| tasks.get(task_id))
+                        .filter(|task| {
+                            task.is_active() && !backoff_clients.contains(&task.client_id)
+                        })
+                        .cloned()
+                        .collect()
+                };
+
+                if current_counter % STALE_CHECK_TICKS == 0 {
+                    let active_tasks: Vec<TaskDefinition> = tasks
+                        .lock()
+                        .await
+                        .values()
+                        .filter(|task| task.is_active())
+                        .cloned()
+                        .collect();
+                    Self::check_stale(&active_tasks).await;
+                }
+
+                let stats_interval_ms = *stats_interval_ms.lock().await;
+                if stats_interval_ms > 0 && current_counter % stats_interval_ms == 0 {
+                    notify_scheduler_stats(TASK_SCHEDULER.get_stats().await);
+                }
+
+                // 合并为块读取后执行
+                if !due_tasks.is_empty() {
+                    let config = *read_optimizer.lock().await;
+                    for block in build_blocks(due_tasks, &config) {
                         #[cfg(debug_assertions)]
                         println!(
-                            "执行任务 - Client ID: {}, Address: {}, Interval: {}",
-                            task.client_id, task.address, task.interval_ms
+                            "执行块读取 - Client ID: {}, Address: {}, Quantity: {}, Tasks: {}",
+                            block.client_id,
+                            block.address,
+                            block.quantity,
+                            block.tasks.len()
                         );
 
-                        Self::execute_task(task, running_clone.clone()).await;
+                        Self::execute_block(&block, running_clone.clone()).await;
                     }
                 }
+
+                // 向退避中的设备发送探测请求，响应后恢复正常轮询
+                for probe in Self::due_probes(&tasks).await {
+                    #[cfg(debug_assertions)]
+                    println!(
+                        "探测设备 - Client ID: {}, Address: {}",
+                        probe.client_id, probe.address
+                    );
+
+                    Self::execute_block(&ReadBlock::single(probe), running_clone.clone()).await;
+                }
+
+                // 执行到期的延时读取和已触发的读取
+                Self::run_triggers(running_clone.clone()).await;
+
+                // 批量模式下推送本周期产生的变化
+                flush_batch();
             }
         });
 
@@ -306,7 +1380,7 @@ impl TaskScheduler {
         Ok(())
     }
 
-    async fn execute_task(task: &TaskDefinition, running: Arc<Mutex<bool>>) {
+    async fn execute_block(block: &ReadBlock, running: Arc<Mutex<bool>>) {
         // 获取执行锁，确保同一时间只有一个任务在执行
         let _lock = TASK_SCHEDULER.execution_lock.lock().await;
 
@@ -315,32 +1389,468 @@ impl TaskScheduler {
             return;
         }
 
-        let client_id = task.client_id;
-        let address = task.address;
-        let read_only = task.read_only;
+        // 轮询请求排队超过一个周期即丢弃，下个周期会重新读取
+        let max_wait = block.tasks.iter().map(|task| task.interval_ms).min();
+        let priority = Priority::Poll {
+            max_wait: Duration::from_millis(max_wait.unwrap_or_default()),
+        };
+
+        let (result, timing) = timed_read_block(block, priority).await;
+        if matches!(&result, Err(e) if e.is_dropped()) {
+            return;
+        }
+        Self::record_health(block.client_id, &result).await;
 
-        match task.data_type {
-            DataType::Bool => {
-                if let Ok(value) = read_bool(client_id, address).await {
-                    notify_bool(client_id, address, value);
+        match result {
+            Ok(data) => {
+                Self::record_stats(block, timing, true).await;
+                Self::fan_out(block, &data, timing).await
+            }
+            // 块中间的空隙可能包含设备不支持的地址，逐个任务重试
+            Err(e) if block.tasks.len() > 1 && e.quality() == Quality::BadException => {
+                Self::record_connection_stats(block.client_id, timing, false, 0).await;
+                for single in block.split() {
+                    let (result, timing) = timed_read_block(&single, priority).await;
+                    if matches!(&result, Err(e) if e.is_dropped()) {
+                        continue;
+                    }
+                    Self::record_health(single.client_id, &result).await;
+                    Self::record_stats(&single, timing, result.is_ok()).await;
+                    match result {
+                        Ok(data) => Self::fan_out(&single, &data, timing).await,
+                        Err(e) => {
+                            let task = &single.tasks[0];
+                            Self::report_error(task, e.quality(), e.to_string(), timing.timestamp)
+                                .await
+                        }
+                    }
                 }
             }
-            DataType::Word => {
-                if let Ok(value) = read_word(client_id, address, read_only).await {
-                    notify_word(client_id, address, read_only, value);
+            Err(e) => {
+                Self::record_stats(block, timing, false).await;
+                for task in &block.tasks {
+                    Self::report_error(task, e.quality(), e.to_string(), timing.timestamp).await;
+                }
+            }
+        }
+    }
+
+    async fn backoff_clients() -> HashSet<i64> {
+        TASK_SCHEDULER
+            .device_health
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, health)| health.backoff)
+            .map(|(client_id, _)| *client_id)
+            .collect()
+    }
+
+    // 根据请求结果更新设备健康状态，进入或退出退避时推送状态事件
+    async fn record_health(client_id: i64, result: &Result<BlockData>) {
+        // 异常响应说明设备在线，只有通信失败才计入连续失败次数
+        let responded = match result {
+            Ok(_) => true,
+            Err(e) => e.quality() == Quality::BadException,
+        };
+        let config = *TASK_SCHEDULER.backoff_config.lock().await;
+
+        let transition = {
+            let mut device_health = TASK_SCHEDULER.device_health.lock().await;
+            let health = device_health.entry(client_id).or_default();
+            if responded {
+                health.consecutive_failures = 0;
+                if health.backoff {
+                    health.backoff = false;
+                    Some((DeviceState::Online, 0))
+                } else {
+                    None
+                }
+            } else {
+                health.consecutive_failures += 1;
+                if config.failure_threshold > 0
+                    && !health.backoff
+                    && health.consecutive_failures >= config.failure_threshold
+                {
+                    health.backoff = true;
+                    health.last_probe = Some(Instant::now());
+                    Some((DeviceState::Backoff, health.consecutive_failures))
+                } else {
+                    None
+                }
+            }
+        };
+
+        if let Some((state, consecutive_failures)) = transition {
+            notify_device_state(client_id, state, consecutive_failures, unix_millis());
+        }
+    }
+
+    // 选出到期需要探测的设备，每个设备取其地址最小的任务作为探测请求
+    async fn due_probes(tasks: &Mutex<HashMap<TaskKey, TaskDefinition>>) -> Vec<TaskDefinition> {
+        let probe_interval =
+            Duration::from_millis(TASK_SCHEDULER.backoff_config.lock().await.probe_interval_ms);
+        let clients: Vec<i64> = {
+            let now = Instant::now();
+            let mut device_health = TASK_SCHEDULER.device_health.lock().await;
+            device_health
+                .iter_mut()
+                .filter(|(_, health)| {
+                    health.backoff
+                        && match health.last_probe {
+                            Some(last_probe) => now.duration_since(last_probe) >= probe_interval,
+                            None => true,
+                        }
+                })
+                .map(|(client_id, health)| {
+                    health.last_probe = Some(now);
+                    *client_id
+                })
+                .collect()
+        };
+        if clients.is_empty() {
+            return Vec::new();
+        }
+
+        let tasks = tasks.lock().await;
+        clients
+            .iter()
+            .filter_map(|client_id| {
+                tasks
+                    .values()
+                    .filter(|task| task.client_id == *client_id && task.is_active())
+                    .min_by_key(|task| task.address)
+                    .cloned()
+            })
+            .collect()
+    }
+
+    // 记录块中各任务及其连接的计时统计
+    async fn record_stats(block: &ReadBlock, timing: ReadTiming, success: bool) {
+        let mut overruns = 0;
+        {
+            let mut task_states = TASK_SCHEDULER.task_states.lock().await;
+            for task in &block.tasks {
+                let state = task_states.entry(task.key()).or_default();
+                if state
+                    .timing
+                    .record(timing.started, task.interval_ms, timing.latency_ms, success)
+                {
+                    overruns += 1;
+                }
+            }
+        }
+        Self::record_connection_stats(block.client_id, timing, success, overruns).await;
+    }
+
+    async fn record_connection_stats(
+        client_id: i64,
+        timing: ReadTiming,
+        success: bool,
+        overruns: u64,
+    ) {
+        let mut connection_stats = TASK_SCHEDULER.connection_stats.lock().await;
+        connection_stats
+            .entry(client_id)
+            .or_default()
+            .record(timing.latency_ms, success, overruns);
+    }
+
+    pub async fn get_stats(&self) -> SchedulerStats {
+        let tasks = self.tasks.lock().await;
+        let task_states = self.task_states.lock().await;
+        let task_stats = tasks
+            .iter()
+            .map(|(task_key, task)| {
+                let timing = task_states
+                    .get(task_key)
+                    .map(|state| state.timing.clone())
+                    .unwrap_or_default();
+                TaskStats {
+                    client_id: task.client_id,
+                    address: task.address,
+                    data_type: task.data_type as u8,
+                    read_only: task.read_only,
+                    interval_ms: task.interval_ms,
+                    period_ms: timing.period_ms,
+                    jitter_ms: timing.jitter_ms,
+                    execution_ms: timing.execution_ms,
+                    overruns: timing.overruns,
+                    successes: timing.successes,
+                    failures: timing.failures,
+                }
+            })
+            .collect();
+
+        let connections = self
+            .connection_stats
+            .lock()
+            .await
+            .iter()
+            .map(|(client_id, timing)| ConnectionStats {
+                client_id: *client_id,
+                timing: timing.clone(),
+            })
+            .collect();
+
+        SchedulerStats {
+            tasks: task_stats,
+            connections,
+        }
+    }
+
+    pub async fn reset_stats(&self) -> Result<()> {
+        for state in self.task_states.lock().await.values_mut() {
+            state.timing = TaskTiming::default();
+        }
+        self.connection_stats.lock().await.clear();
+        Ok(())
+    }
+
+    // 设置统计事件的推送周期，为零时不推送
+    pub async fn set_stats_interval(&self, interval_ms: u64) -> Result<()> {
+        *self.stats_interval_ms.lock().await = interval_ms;
+        Ok(())
+    }
+
+    // 将块读取结果分发到各个任务
+    async fn fan_out(block: &ReadBlock, data: &BlockData, timing: ReadTiming) {
+        for task in &block.tasks {
+            let offset = (task.address - block.address) as usize;
+            match PlcValue::decode(task.data_type, data, offset) {
+                Some(value) => {
+                    Self::check_triggers(task, value).await;
+                    Self::publish(task, value, timing).await
+                }
+                None => {
+                    let error = "响应数据长度不足".to_string();
+                    Self::report_error(task, Quality::BadComm, error, timing.timestamp).await
                 }
             }
-            DataType::Dword => {
-                if let Ok(value) = read_dword(client_id, address, read_only).await {
-                    notify_dword(client_id, address, read_only, value);
+        }
+    }
+
+    // 记录任务错误，质量或错误信息变化时推送错误事件
+    async fn report_error(task: &TaskDefinition, quality: Quality, error: String, timestamp: u64) {
+        {
+            let mut task_states = TASK_SCHEDULER.task_states.lock().await;
+            let state = task_states.entry(task.key()).or_default();
+            if state.quality == quality && state.last_error.as_deref() == Some(error.as_str()) {
+                return;
+            }
+            state.quality = quality;
+            state.last_error = Some(error.clone());
+        }
+
+        tags::publish_error(task, quality, &error, timestamp).await;
+        notify_task_error(
+            task.client_id,
+            task.address,
+            task.data_type as u8,
+            task.read_only,
+            quality,
+            error,
+            timestamp,
+        );
+    }
+
+    // 将长时间未成功更新的任务标记为过期
+    async fn check_stale(tasks: &[TaskDefinition]) {
+        let now = Instant::now();
+        let stale_tasks: Vec<&TaskDefinition> = {
+            let task_states = TASK_SCHEDULER.task_states.lock().await;
+            tasks
+                .iter()
+                .filter(|task| match task_states.get(&task.key()) {
+                    Some(TaskState {
+                        quality: Quality::Good,
+                        last_update: Some(last_update),
+                        ..
+                    }) => {
+                        now.duration_since(*last_update)
+                            > Duration::from_millis(task.interval_ms) * STALE_INTERVAL_FACTOR
+                    }
+                    _ => false,
+                })
+                .collect()
+        };
+
+        for task in stale_tasks {
+            let error = "数据超时未更新".to_string();
+            Self::report_error(task, Quality::Stale, error, unix_millis()).await;
+        }
+    }
+
+    // 根据任务前后两次读取的值判断是否满足触发条件
+    async fn check_triggers(task: &TaskDefinition, value: PlcValue) {
+        let task_key = task.key();
+        let last = {
+            let mut task_states = TASK_SCHEDULER.task_states.lock().await;
+            let state = task_states.entry(task_key).or_default();
+            state.last_read.replace(value)
+        };
+        let Some(last) = last else {
+            return;
+        };
+
+        let fired: Vec<u64> = TASK_SCHEDULER
+            .triggers
+            .lock()
+            .await
+            .values()
+            .filter(|trigger| match &trigger.condition {
+                TriggerCondition::OnChange { source, edge } => {
+                    *source == task_key && edge.matches(&last, &value)
                 }
+                TriggerCondition::Delay { .. } => false,
+            })
+            .map(|trigger| trigger.id)
+            .collect();
+
+        if !fired.is_empty() {
+            TASK_SCHEDULER.pending_triggers.lock().await.extend(fired);
+        }
+    }
+
+    async fn run_triggers(running: Arc<Mutex<bool>>) {
+        let now = Instant::now();
+        let due: Vec<TriggerDefinition> = {
+            let mut triggers = TASK_SCHEDULER.triggers.lock().await;
+            let mut pending = TASK_SCHEDULER.pending_triggers.lock().await;
+            let mut due: Vec<TriggerDefinition> = pending
+                .drain(..)
+                .filter_map(|id| triggers.get(&id).cloned())
+                .collect();
+
+            // 延时读取只执行一次，到期后移除
+            let expired: Vec<u64> = triggers
+                .values()
+                .filter(|trigger| {
+                    matches!(trigger.condition, TriggerCondition::Delay { fire_at } if fire_at <= now)
+                })
+                .map(|trigger| trigger.id)
+                .collect();
+            due.extend(expired.iter().filter_map(|id| triggers.remove(id)));
+            due
+        };
+
+        for trigger in due {
+            // 获取执行锁，与周期任务串行执行
+            let _lock = TASK_SCHEDULER.execution_lock.lock().await;
+            if !*running.lock().await {
+                return;
             }
-            DataType::Float => {
-                if let Ok(value) = read_float(client_id, address, read_only).await {
-                    notify_float(client_id, address, read_only, value);
+
+            #[cfg(debug_assertions)]
+            println!(
+                "执行触发读取 - ID: {}, Client ID: {}, Address: {}, Quantity: {}",
+                trigger.id, trigger.client_id, trigger.address, trigger.quantity
+            );
+
+            let block = trigger.block();
+            let (result, timing) = timed_read_block(&block, Priority::Read).await;
+            let (values, quality, error) = match result {
+                Ok(data) => {
+                    let width = trigger.data_type.width() as usize;
+                    let values = (0..trigger.quantity as usize)
+                        .filter_map(|i| PlcValue::decode(trigger.data_type, &data, i * width))
+                        .collect();
+                    (values, Quality::Good, None)
                 }
+                Err(e) => (Vec::new(), e.quality(), Some(e.to_string())),
+            };
+
+            notify_trigger_read(
+                trigger.id,
+                trigger.client_id,
+                trigger.address,
+                trigger.data_type as u8,
+                trigger.read_only,
+                values,
+                quality,
+                error,
+                timing.timestamp,
+            );
+        }
+    }
+
+    async fn publish(task: &TaskDefinition, value: PlcValue, timing: ReadTiming) {
+        // 历史记录与最近值缓存一样不经过变化过滤；有标签时按标签换算后记录
+        let engineering = tags::engineering_value(task, value).await;
+        historian::record(
+            task,
+            engineering.unwrap_or(value.as_f64()),
+            timing.timestamp,
+        );
+
+        // 按变化和死区过滤，超过完整性周期时无论是否变化都推送
+        {
+            let integrity_interval_ms = *TASK_SCHEDULER.integrity_interval_ms.lock().await;
+            let mut task_states = TASK_SCHEDULER.task_states.lock().await;
+            let state = task_states.entry(task.key()).or_default();
+            let now = Instant::now();
+
+            // 按时间和数量两个上限裁剪
+            state.recent.push_back((timing.timestamp, value));
+            let oldest = timing.timestamp.saturating_sub(RECENT_WINDOW_MS);
+            while state.recent.len() > MAX_RECENT_SAMPLES
+                || state.recent.front().is_some_and(|(ts, _)| *ts < oldest)
+            {
+                state.recent.pop_front();
+            }
+
+            // 质量恢复时必须推送，让前端及时取消异常显示
+            let recovered = state.quality != Quality::Good;
+            state.quality = Quality::Good;
+            state.last_error = None;
+            state.last_update = Some(now);
+
+            let changed = match state.last_value {
+                Some(last) => recovered || value.changed(&last, task.deadband),
+                None => true,
+            };
+            let integrity_due = integrity_interval_ms > 0
+                && match state.last_emit {
+                    Some(last_emit) => {
+                        now.duration_since(last_emit)
+                            >= Duration::from_millis(integrity_interval_ms)
+                    }
+                    None => true,
+                };
+            if !changed && !integrity_due {
+                return;
+            }
+            state.last_value = Some(value);
+            state.last_emit = Some(now);
+        }
+
+        let client_id = task.client_id;
+        let address = task.address;
+        let read_only = task.read_only;
+
+        let quality = Quality::Good;
+        let ReadTiming {
+            timestamp,
+            latency_ms,
+            ..
+        } = timing;
+
+        match value {
+            PlcValue::Bool(value) => {
+                notify_bool(client_id, address, value, quality, timestamp, latency_ms)
             }
+            PlcValue::Word(value) => notify_word(
+                client_id, address, read_only, value, quality, timestamp, latency_ms,
+            ),
+            PlcValue::Dword(value) => notify_dword(
+                client_id, address, read_only, value, quality, timestamp, latency_ms,
+            ),
+            PlcValue::Float(value) => notify_float(
+                client_id, address, read_only, value, quality, timestamp, latency_ms,
+            ),
         }
+
+        tags::publish_value(task, value, timestamp).await;
     }
 
     pub async fn stop(&self) -> Result<()> {
@@ -356,6 +1866,14 @@ impl TaskScheduler {
             handle.abort();
         }
 
+        // 停止后所有任务不再更新，统一标记为过期
+        let all_tasks: Vec<TaskDefinition> = self.tasks.lock().await.values().cloned().collect();
+        let timestamp = unix_millis();
+        for task in &all_tasks {
+            let error = "调度器已停止".to_string();
+            Self::report_error(task, Quality::Stale, error, timestamp).await;
+        }
+
         Ok(())
     }
 }
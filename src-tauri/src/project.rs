@@ -0,0 +1,270 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::historian;
+use crate::modbus::{ConnectionConfig, MODBUS_MANAGER};
+use crate::plc::{DataType, Deadband, PLCError, TASK_SCHEDULER};
+use crate::tags::{self, TagDefinition};
+
+type Result<T> = std::result::Result<T, PLCError>;
+
+// 当前的项目文件格式版本，格式不兼容时递增
+pub const PROJECT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectConnection {
+    // 保存时的连接 ID，加载后连接 ID 可能变化，任务和标签通过它对应到新连接
+    pub id: String,
+    #[serde(flatten)]
+    pub config: ConnectionConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectTask {
+    pub client_id: String,
+    pub address: u16,
+    pub data_type: u8,
+    pub read_only: bool,
+    pub interval_ms: u64,
+    #[serde(default)]
+    pub deadband_type: u8,
+    #[serde(default)]
+    pub deadband: f64,
+    #[serde(default)]
+    pub paused: bool,
+    // 历史数据的保留时长，为零时不记录历史
+    #[serde(default)]
+    pub history_retention_ms: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    pub version: u32,
+    pub connections: Vec<ProjectConnection>,
+    pub tasks: Vec<ProjectTask>,
+    #[serde(default)]
+    pub tags: Vec<TagDefinition>,
+}
+
+/// 加载时单个连接、任务或标签的结果
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadItem {
+    pub item: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+impl LoadItem {
+    fn new(item: String, result: std::result::Result<(), String>) -> Self {
+        LoadItem {
+            item,
+            success: result.is_ok(),
+            error: result.err(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadReport {
+    pub connections: Vec<LoadItem>,
+    pub tasks: Vec<LoadItem>,
+    pub tags: Vec<LoadItem>,
+}
+
+fn describe(config: &ConnectionConfig) -> String {
+    match config {
+        ConnectionConfig::Tcp { ip, port } => format!("{}:{}", ip, port),
+        ConnectionConfig::Serial { serial_port, .. } => serial_port.clone(),
+    }
+}
+
+/// 收集当前的连接、任务和标签
+pub async fn snapshot() -> Project {
+    let mut connections: Vec<ProjectConnection> = MODBUS_MANAGER
+        .connection_configs()
+        .await
+        .into_iter()
+        .map(|(client_id, config)| ProjectConnection {
+            id: client_id.to_string(),
+            config,
+        })
+        .collect();
+    connections.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut tasks: Vec<ProjectTask> = Vec::new();
+    for task in TASK_SCHEDULER.task_definitions().await {
+        let (deadband_type, deadband) = Deadband::to_type(task.deadband);
+        tasks.push(ProjectTask {
+            client_id: task.client_id.to_string(),
+            address: task.address,
+            data_type: task.data_type as u8,
+            read_only: task.read_only,
+            interval_ms: task.interval_ms,
+            deadband_type,
+            deadband,
+            paused: task.paused,
+            history_retention_ms: historian::retention_of(&task.key()).await.unwrap_or(0),
+        });
+    }
+    tasks.sort_by(|a, b| (&a.client_id, a.address).cmp(&(&b.client_id, b.address)));
+
+    Project {
+        version: PROJECT_VERSION,
+        connections,
+        tasks,
+        tags: tags::list_tags().await,
+    }
+}
+
+pub async fn save(path: &Path) -> Result<()> {
+    let project = snapshot().await;
+    let content = serde_json::to_string_pretty(&project)
+        .map_err(|e| PLCError::Other(format!("项目序列化失败: {}", e)))?;
+    tokio::fs::write(path, content)
+        .await
+        .map_err(|e| PLCError::Other(format!("项目保存失败: {}", e)))
+}
+
+// 读取并校验项目文件，版本不支持时不做任何修改
+async fn read_project(path: &Path) -> Result<Project> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| PLCError::Other(format!("项目读取失败: {}", e)))?;
+    let value: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| PLCError::Other(format!("项目文件格式错误: {}", e)))?;
+
+    let version = value
+        .get("version")
+        .and_then(|version| version.as_u64())
+        .ok_or_else(|| PLCError::Other("项目文件缺少版本号".to_string()))?;
+    if version == 0 || version > PROJECT_VERSION as u64 {
+        return Err(PLCError::Other(format!(
+            "不支持的项目版本: {}，当前支持 {}",
+            version, PROJECT_VERSION
+        )));
+    }
+
+    serde_json::from_value(value).map_err(|e| PLCError::Other(format!("项目文件格式错误: {}", e)))
+}
+
+/// 按项目文件重建连接、任务和标签，单项失败不影响其他项
+pub async fn load(path: &Path) -> Result<LoadReport> {
+    let project = read_project(path).await?;
+    let mut report = LoadReport::default();
+
+    // 保存时的连接 ID 到新连接 ID
+    let mut client_ids: HashMap<String, i64> = HashMap::new();
+    for connection in &project.connections {
+        let result = match MODBUS_MANAGER.connect(&connection.config).await {
+            Ok(client_id) => {
+                client_ids.insert(connection.id.clone(), client_id);
+                TASK_SCHEDULER
+                    .restore_client_tasks(client_id)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
+            Err(e) => Err(e.to_string()),
+        };
+        report
+            .connections
+            .push(LoadItem::new(describe(&connection.config), result));
+    }
+
+    // 成功注册的任务及其新连接 ID，待标签恢复后再应用死区和暂停状态
+    let mut loaded: Vec<(usize, i64)> = Vec::new();
+    for (index, task) in project.tasks.iter().enumerate() {
+        let item = format!("{} @ {}", task.client_id, task.address);
+        let result = match client_ids.get(&task.client_id) {
+            Some(client_id) => {
+                let result = load_task(*client_id, task).await.map_err(|e| e.to_string());
+                if result.is_ok() {
+                    loaded.push((index, *client_id));
+                }
+                result
+            }
+            None => Err("所属连接未建立".to_string()),
+        };
+        report.tasks.push(LoadItem::new(item, result));
+    }
+
+    for tag in project.tags {
+        let item = tag.name.clone();
+        let result = match client_ids.get(&tag.client_id) {
+            Some(client_id) => {
+                let tag = TagDefinition {
+                    client_id: client_id.to_string(),
+                    ..tag
+                };
+                tags::define_tag(tag).await.map_err(|e| e.to_string())
+            }
+            None => Err("所属连接未建立".to_string()),
+        };
+        report.tags.push(LoadItem::new(item, result));
+    }
+
+    // 标签会重新注册其轮询任务并清除暂停状态，因此最后应用
+    for (index, client_id) in loaded {
+        if let Err(e) = restore_task_state(client_id, &project.tasks[index]).await {
+            report.tasks[index].success = false;
+            report.tasks[index].error = Some(e.to_string());
+        }
+    }
+
+    Ok(report)
+}
+
+async fn load_task(client_id: i64, task: &ProjectTask) -> Result<()> {
+    TASK_SCHEDULER
+        .register_task(
+            client_id,
+            task.interval_ms,
+            task.address,
+            task.data_type,
+            task.read_only,
+        )
+        .await
+}
+
+async fn restore_task_state(client_id: i64, task: &ProjectTask) -> Result<()> {
+    let deadband = Deadband::from_type(task.deadband_type, task.deadband);
+    TASK_SCHEDULER
+        .set_task_deadband(
+            client_id,
+            task.address,
+            task.data_type,
+            task.read_only,
+            deadband,
+        )
+        .await?;
+
+    if task.history_retention_ms > 0 {
+        historian::enable(
+            client_id,
+            task.address,
+            DataType::from(task.data_type),
+            task.read_only,
+            task.history_retention_ms,
+        )
+        .await?;
+    }
+
+    if task.paused {
+        TASK_SCHEDULER
+            .set_task_paused(
+                client_id,
+                task.address,
+                task.data_type,
+                task.read_only,
+                true,
+            )
+            .await?;
+    }
+    Ok(())
+}
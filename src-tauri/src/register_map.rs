@@ -0,0 +1,395 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::plc::{
+    self, AddressArea, AddressRef, DataType, PLCError, RegisterTable, TASK_SCHEDULER,
+};
+use crate::tags::{self, TagDefinition, WordOrder};
+
+type Result<T> = std::result::Result<T, PLCError>;
+
+// 导出时的列顺序，导入时按列名识别，不要求顺序一致
+const COLUMNS: [&str; 10] = [
+    "name",
+    "address",
+    "table",
+    "type",
+    "word_order",
+    "scale",
+    "offset",
+    "unit",
+    "interval",
+    "description",
+];
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RowError {
+    // 文件中的行号，表头为第 1 行
+    pub line: u64,
+    pub error: String,
+}
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub total_rows: usize,
+    pub imported: usize,
+    pub errors: Vec<RowError>,
+}
+
+// 把表头映射为标准列名，兼容厂商表格中常见的写法
+fn column_name(header: &str) -> Option<&'static str> {
+    let header = header.trim().to_lowercase().replace([' ', '-'], "_");
+    let name = match header.as_str() {
+        "name" | "tag" | "名称" => "name",
+        "address" | "addr" | "地址" => "address",
+        "table" | "function" | "function_code" | "fc" | "功能码" => "table",
+        "type" | "data_type" | "类型" => "type",
+        "word_order" | "byte_order" | "字序" => "word_order",
+        "scale" | "gain" | "系数" => "scale",
+        "offset" | "bias" | "偏移" => "offset",
+        "unit" | "units" | "单位" => "unit",
+        "interval" | "interval_ms" | "周期" => "interval",
+        "description" | "desc" | "comment" | "描述" => "description",
+        _ => return None,
+    };
+    Some(name)
+}
+
+// 原始偏移的十进制写法最多 4 位，更长的数字按 Modicon 编号解析
+const MAX_RAW_DIGITS: usize = 4;
+
+// 十六进制和不超过 4 位的十进制数为原始偏移，其余按 40001、%MW100、HR100 等地址引用解析，
+// 地址引用同时给出寄存器表
+fn parse_address(value: &str) -> std::result::Result<(u16, Option<RegisterTable>), String> {
+    if let Some(hex) = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        let offset = u16::from_str_radix(hex, 16).map_err(|_| format!("无效的地址: {}", value))?;
+        return Ok((offset, None));
+    }
+    if value.len() <= MAX_RAW_DIGITS && value.bytes().all(|b| b.is_ascii_digit()) {
+        let offset = value
+            .parse()
+            .map_err(|_| format!("无效的地址: {}", value))?;
+        return Ok((offset, None));
+    }
+
+    let resolved = plc::parse_address(value).map_err(|e| e.to_string())?;
+    let table = match resolved.area {
+        AddressArea::Coil => RegisterTable::Coil,
+        AddressArea::Holding => RegisterTable::Holding,
+        AddressArea::Input => RegisterTable::Input,
+        AddressArea::DiscreteInput => return Err(format!("暂不支持离散输入地址: {}", value)),
+    };
+    Ok((resolved.offset, Some(table)))
+}
+
+// 导出时大于 4 位的偏移写成十六进制，避免导入时被当作 Modicon 编号
+fn format_address(offset: u16) -> String {
+    if offset.to_string().len() <= MAX_RAW_DIGITS {
+        offset.to_string()
+    } else {
+        format!("0x{:04X}", offset)
+    }
+}
+
+fn parse_table(value: &str) -> std::result::Result<RegisterTable, String> {
+    match value.to_lowercase().as_str() {
+        "1" | "coil" | "coils" => Ok(RegisterTable::Coil),
+        "3" | "holding" | "holding_register" => Ok(RegisterTable::Holding),
+        "4" | "input" | "input_register" => Ok(RegisterTable::Input),
+        _ => Err(format!("不支持的寄存器表: {}", value)),
+    }
+}
+
+fn parse_type(value: &str) -> std::result::Result<DataType, String> {
+    match value.to_lowercase().as_str() {
+        "bool" | "bit" => Ok(DataType::Bool),
+        "word" | "u16" | "uint16" => Ok(DataType::Word),
+        "dword" | "u32" | "uint32" => Ok(DataType::Dword),
+        "float" | "f32" | "real" => Ok(DataType::Float),
+        _ => Err(format!("不支持的数据类型: {}", value)),
+    }
+}
+
+fn parse_word_order(value: &str) -> std::result::Result<WordOrder, String> {
+    match value.to_lowercase().as_str() {
+        "" | "low" | "low_first" | "cdab" => Ok(WordOrder::LowFirst),
+        "high" | "high_first" | "abcd" => Ok(WordOrder::HighFirst),
+        _ => Err(format!("不支持的字序: {}", value)),
+    }
+}
+
+// 解析一行为标签定义
+fn parse_row(
+    row: &HashMap<&'static str, String>,
+    client_id: i64,
+    default_interval_ms: u64,
+) -> std::result::Result<TagDefinition, String> {
+    let field = |name: &str| row.get(name).map(|value| value.trim()).unwrap_or("");
+
+    let name = field("name");
+    if name.is_empty() {
+        return Err("缺少名称".to_string());
+    }
+    let (address, referenced) = parse_address(field("address"))?;
+    // 地址引用自带寄存器表，此时 table 列可以留空，填写时必须一致
+    let table = match (referenced, field("table")) {
+        (Some(table), "") => table,
+        (Some(table), value) => {
+            if parse_table(value)? != table {
+                return Err(format!(
+                    "地址 {} 与寄存器表 {} 不一致",
+                    field("address"),
+                    value
+                ));
+            }
+            table
+        }
+        (None, "") => return Err("原始偏移地址需要填写寄存器表".to_string()),
+        (None, value) => parse_table(value)?,
+    };
+    let data_type = parse_type(field("type"))?;
+    if (table == RegisterTable::Coil) != (data_type == DataType::Bool) {
+        return Err("线圈只能使用布尔类型，寄存器不能使用布尔类型".to_string());
+    }
+    let word_order = parse_word_order(field("word_order"))?;
+
+    let scale = match field("scale") {
+        "" => 1.0,
+        value => value
+            .parse::<f64>()
+            .ok()
+            .filter(|scale| *scale != 0.0 && scale.is_finite())
+            .ok_or_else(|| format!("无效的缩放系数: {}", value))?,
+    };
+    let offset = match field("offset") {
+        "" => 0.0,
+        value => value
+            .parse::<f64>()
+            .ok()
+            .filter(|offset| offset.is_finite())
+            .ok_or_else(|| format!("无效的偏移量: {}", value))?,
+    };
+    let interval_ms = match field("interval") {
+        "" => default_interval_ms,
+        value => value
+            .parse()
+            .map_err(|_| format!("无效的采集周期: {}", value))?,
+    };
+
+    Ok(TagDefinition {
+        name: name.to_string(),
+        description: field("description").to_string(),
+        client_id: client_id.to_string(),
+        address: AddressRef::Offset(address),
+        data_type: data_type as u8,
+        read_only: table == RegisterTable::Input,
+        word_order: word_order as u8,
+        scale,
+        offset,
+        unit: field("unit").to_string(),
+        interval_ms,
+    })
+}
+
+/// 从 CSV 导入寄存器表，dry_run 时只校验不注册
+pub async fn import_csv(
+    path: &Path,
+    client_id: i64,
+    default_interval_ms: u64,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .map_err(|e| PLCError::Other(format!("CSV 读取失败: {}", e)))?;
+
+    let headers: Vec<Option<&'static str>> = reader
+        .headers()
+        .map_err(|e| PLCError::Other(format!("CSV 表头错误: {}", e)))?
+        .iter()
+        .map(column_name)
+        .collect();
+    for required in ["name", "address", "type"] {
+        if !headers.contains(&Some(required)) {
+            return Err(PLCError::Other(format!("CSV 缺少必需的列: {}", required)));
+        }
+    }
+
+    let mut report = ImportReport::default();
+    let mut definitions = Vec::new();
+    let mut names = HashSet::new();
+    for record in reader.records() {
+        report.total_rows += 1;
+        // 带引号的字段可以跨行，行号取记录实际开始的位置
+        let position = match &record {
+            Ok(record) => record.position(),
+            Err(e) => e.position(),
+        };
+        let line = position
+            .map(|position| position.line())
+            .unwrap_or(report.total_rows as u64 + 1);
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                report.errors.push(RowError {
+                    line,
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        let row: HashMap<&'static str, String> = headers
+            .iter()
+            .zip(record.iter())
+            .filter_map(|(column, value)| column.map(|column| (column, value.to_string())))
+            .collect();
+        let parsed = parse_row(&row, client_id, default_interval_ms).and_then(|definition| {
+            if names.insert(definition.name.clone()) {
+                Ok(definition)
+            } else {
+                Err(format!("名称重复: {}", definition.name))
+            }
+        });
+        match parsed {
+            Ok(definition) => definitions.push((line, definition)),
+            Err(error) => report.errors.push(RowError { line, error }),
+        }
+    }
+
+    // 试运行与实际导入做相同的校验，包括与已有标签的冲突
+    if dry_run {
+        let (lines, definitions): (Vec<u64>, Vec<TagDefinition>) = definitions.into_iter().unzip();
+        for (line, result) in lines
+            .into_iter()
+            .zip(tags::validate_tags(definitions).await)
+        {
+            match result {
+                Ok(()) => report.imported += 1,
+                Err(e) => report.errors.push(RowError {
+                    line,
+                    error: e.to_string(),
+                }),
+            }
+        }
+        return Ok(report);
+    }
+
+    for (line, definition) in definitions {
+        match tags::define_tag(definition).await {
+            Ok(()) => report.imported += 1,
+            Err(e) => report.errors.push(RowError {
+                line,
+                error: e.to_string(),
+            }),
+        }
+    }
+    Ok(report)
+}
+
+fn table_name(table: RegisterTable) -> &'static str {
+    match table {
+        RegisterTable::Coil => "coil",
+        RegisterTable::Holding => "holding",
+        RegisterTable::Input => "input",
+    }
+}
+
+fn type_name(data_type: DataType) -> &'static str {
+    match data_type {
+        DataType::Bool => "bool",
+        DataType::Word => "word",
+        DataType::Dword => "dword",
+        DataType::Float => "float",
+    }
+}
+
+/// 把连接的标签和没有标签的任务导出为 CSV，导出的文件可以原样导入
+pub async fn export_csv(path: &Path, client_id: i64) -> Result<usize> {
+    let client = client_id.to_string();
+    let mut definitions: Vec<TagDefinition> = tags::list_tags()
+        .await
+        .into_iter()
+        .filter(|tag| tag.client_id == client)
+        .collect();
+
+    // 没有标签的任务按数据区和地址生成名称，与已有名称重复时追加序号
+    let mut names: HashSet<String> = definitions.iter().map(|tag| tag.name.clone()).collect();
+    for task in TASK_SCHEDULER.task_definitions().await {
+        if task.client_id != client_id || tags::find_for_task(&task).await.is_some() {
+            continue;
+        }
+        let base = format!("{}_{}", table_name(task.table()), task.address);
+        let mut name = base.clone();
+        let mut suffix = 1;
+        while !names.insert(name.clone()) {
+            suffix += 1;
+            name = format!("{}_{}", base, suffix);
+        }
+        definitions.push(TagDefinition {
+            name,
+            description: String::new(),
+            client_id: client.clone(),
+            address: AddressRef::Offset(task.address),
+            data_type: task.data_type as u8,
+            read_only: task.read_only,
+            word_order: WordOrder::LowFirst as u8,
+            scale: 1.0,
+            offset: 0.0,
+            unit: String::new(),
+            interval_ms: task.interval_ms,
+        });
+    }
+
+    let table_of =
+        |tag: &TagDefinition| RegisterTable::of(DataType::from(tag.data_type), tag.read_only);
+    // 已定义的标签地址都保存为原始偏移
+    let offset_of = |tag: &TagDefinition| {
+        tag.address
+            .resolve(DataType::from(tag.data_type), tag.read_only)
+            .map(|(offset, _)| offset)
+            .unwrap_or_default()
+    };
+    definitions.sort_by(|a, b| {
+        (table_of(a) as u8, offset_of(a), &a.name).cmp(&(table_of(b) as u8, offset_of(b), &b.name))
+    });
+
+    let mut writer = csv::Writer::from_path(path)
+        .map_err(|e| PLCError::Other(format!("CSV 创建失败: {}", e)))?;
+    writer
+        .write_record(COLUMNS)
+        .map_err(|e| PLCError::Other(format!("CSV 写入失败: {}", e)))?;
+
+    for tag in &definitions {
+        let word_order = match WordOrder::from(tag.word_order) {
+            WordOrder::LowFirst => "low",
+            WordOrder::HighFirst => "high",
+        };
+        writer
+            .write_record([
+                tag.name.clone(),
+                format_address(offset_of(tag)),
+                table_name(table_of(tag)).to_string(),
+                type_name(DataType::from(tag.data_type)).to_string(),
+                word_order.to_string(),
+                tag.scale.to_string(),
+                tag.offset.to_string(),
+                tag.unit.clone(),
+                tag.interval_ms.to_string(),
+                tag.description.clone(),
+            ])
+            .map_err(|e| PLCError::Other(format!("CSV 写入失败: {}", e)))?;
+    }
+
+    writer
+        .flush()
+        .map_err(|e| PLCError::Other(format!("CSV 写入失败: {}", e)))?;
+    Ok(definitions.len())
+}
@@ -0,0 +1,421 @@
+use lazy_static::lazy_static;
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, NativeCallContext, Position};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::runtime::Handle;
+use tokio::sync::Mutex;
+
+use crate::guard;
+use crate::notice::{notify_script_output, notify_script_state};
+use crate::plc::{self, unix_millis, AddressRef, DataType, PLCError};
+use crate::tags;
+
+type Result<T> = std::result::Result<T, PLCError>;
+type ScriptResult<T> = std::result::Result<T, Box<EvalAltResult>>;
+
+// 已结束的脚本最多保留的条数
+const MAX_FINISHED_SCRIPTS: usize = 100;
+// sleep 和 wait_until 检查停止请求的间隔
+const CANCEL_CHECK_MS: u64 = 50;
+// wait_until 重新求值条件的间隔
+const WAIT_POLL_MS: u64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScriptState {
+    Running,
+    Completed,
+    Failed,
+    Stopped,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptInfo {
+    pub id: String,
+    pub name: String,
+    pub state: ScriptState,
+    pub started: u64,
+    pub finished: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScriptOutputKind {
+    // print 和 debug 的输出
+    Print,
+    // log 函数的输出
+    Log,
+    Error,
+}
+
+/// 脚本输出事件
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptOutput {
+    pub id: String,
+    pub kind: ScriptOutputKind,
+    pub message: String,
+    pub timestamp: u64,
+}
+
+struct Script {
+    info: ScriptInfo,
+    cancel: Arc<AtomicBool>,
+}
+
+lazy_static! {
+    static ref SCRIPTS: Mutex<HashMap<String, Script>> = Mutex::new(HashMap::new());
+    static ref NEXT_SCRIPT_ID: AtomicU64 = AtomicU64::new(1);
+}
+
+fn output(id: &str, kind: ScriptOutputKind, message: String) {
+    notify_script_output(ScriptOutput {
+        id: id.to_string(),
+        kind,
+        message,
+        timestamp: unix_millis(),
+    });
+}
+
+// 脚本运行在阻塞线程上，通过运行时句柄调用异步的读写函数
+#[derive(Clone)]
+struct ScriptContext {
+    id: String,
+    handle: Handle,
+    cancel: Arc<AtomicBool>,
+}
+
+impl ScriptContext {
+    fn block_on<T, E: ToString>(
+        &self,
+        future: impl Future<Output = std::result::Result<T, E>>,
+    ) -> ScriptResult<T> {
+        self.handle
+            .block_on(future)
+            .map_err(|e| e.to_string().into())
+    }
+
+    fn check_cancel(&self) -> ScriptResult<()> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(EvalAltResult::ErrorTerminated(Dynamic::UNIT, Position::NONE).into());
+        }
+        Ok(())
+    }
+
+    // 分段休眠以便及时响应停止请求
+    fn sleep(&self, duration: Duration) -> ScriptResult<()> {
+        let deadline = Instant::now() + duration;
+        loop {
+            self.check_cancel()?;
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(());
+            }
+            std::thread::sleep(remaining.min(Duration::from_millis(CANCEL_CHECK_MS)));
+        }
+    }
+
+    fn write(
+        &self,
+        client_id: i64,
+        address: Dynamic,
+        data_type: DataType,
+        value: f64,
+    ) -> ScriptResult<()> {
+        let address = to_address(address)?
+            .resolve_writable(data_type)
+            .map_err(|e| e.to_string())?;
+        self.block_on(guard::check(client_id, address, data_type, value))?;
+        let result = match data_type {
+            DataType::Bool => {
+                self.block_on(plc::write_bool(client_id, address, value != 0.0, None))
+            }
+            DataType::Word => {
+                let value = to_integer(value, u16::MAX as f64)? as u16;
+                self.block_on(plc::write_word(client_id, address, value, None))
+            }
+            DataType::Dword => {
+                let value = to_integer(value, u32::MAX as f64)? as u32;
+                self.block_on(plc::write_dword(client_id, address, value, None))
+            }
+            DataType::Float => {
+                self.block_on(plc::write_float(client_id, address, value as f32, None))
+            }
+        };
+        result?;
+        self.handle
+            .block_on(guard::commit(client_id, address, data_type));
+        Ok(())
+    }
+}
+
+// 地址可以是整数偏移，也可以是 "40001"、"%MW10" 这样的地址引用
+fn to_address(value: Dynamic) -> ScriptResult<AddressRef> {
+    if let Ok(offset) = value.as_int() {
+        return u16::try_from(offset)
+            .map(AddressRef::Offset)
+            .map_err(|_| format!("地址超出范围: {}", offset).into());
+    }
+    match value.into_string() {
+        Ok(text) => Ok(AddressRef::Reference(text)),
+        Err(type_name) => Err(format!("无效的地址类型: {}", type_name).into()),
+    }
+}
+
+fn to_number(value: Dynamic) -> ScriptResult<f64> {
+    if let Ok(value) = value.as_int() {
+        return Ok(value as f64);
+    }
+    if let Ok(value) = value.as_float() {
+        return Ok(value);
+    }
+    match value.as_bool() {
+        Ok(value) => Ok(value as u8 as f64),
+        Err(type_name) => Err(format!("无效的数值类型: {}", type_name).into()),
+    }
+}
+
+fn to_integer(value: f64, max: f64) -> ScriptResult<f64> {
+    if value < 0.0 || value > max || value.fract() != 0.0 {
+        return Err(format!("写入值超出范围: {}", value).into());
+    }
+    Ok(value)
+}
+
+fn build_engine(context: &ScriptContext) -> Engine {
+    let mut engine = Engine::new();
+
+    let id = context.id.clone();
+    engine.on_print(move |text| output(&id, ScriptOutputKind::Print, text.to_string()));
+    let id = context.id.clone();
+    engine.on_debug(move |text, _, position| {
+        output(
+            &id,
+            ScriptOutputKind::Print,
+            format!("[{}] {}", position, text),
+        )
+    });
+    let cancel = context.cancel.clone();
+    engine.on_progress(move |_| cancel.load(Ordering::Relaxed).then_some(Dynamic::UNIT));
+
+    let ctx = context.clone();
+    engine.register_fn("log", move |message: &str| {
+        output(&ctx.id, ScriptOutputKind::Log, message.to_string())
+    });
+    let ctx = context.clone();
+    engine.register_fn("sleep", move |ms: i64| -> ScriptResult<()> {
+        ctx.sleep(Duration::from_millis(ms.max(0) as u64))
+    });
+
+    // 每隔一段时间调用条件函数，满足时返回 true，超时返回 false
+    let ctx = context.clone();
+    engine.register_fn(
+        "wait_until",
+        move |call: NativeCallContext, condition: FnPtr, timeout_ms: i64| -> ScriptResult<bool> {
+            let deadline = Instant::now() + Duration::from_millis(timeout_ms.max(0) as u64);
+            loop {
+                if condition.call_within_context::<bool>(&call, ())? {
+                    return Ok(true);
+                }
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Ok(false);
+                }
+                ctx.sleep(remaining.min(Duration::from_millis(WAIT_POLL_MS)))?;
+            }
+        },
+    );
+
+    let ctx = context.clone();
+    engine.register_fn(
+        "read_bool",
+        move |client_id: i64, address: Dynamic| -> ScriptResult<bool> {
+            let (address, _) = to_address(address)?
+                .resolve(DataType::Bool, false)
+                .map_err(|e| e.to_string())?;
+            ctx.block_on(plc::read_bool(client_id, address))
+        },
+    );
+    let ctx = context.clone();
+    engine.register_fn(
+        "read_word",
+        move |client_id: i64, address: Dynamic| -> ScriptResult<i64> {
+            let (address, read_only) = to_address(address)?
+                .resolve(DataType::Word, false)
+                .map_err(|e| e.to_string())?;
+            ctx.block_on(plc::read_word(client_id, address, read_only))
+                .map(i64::from)
+        },
+    );
+    let ctx = context.clone();
+    engine.register_fn(
+        "read_dword",
+        move |client_id: i64, address: Dynamic| -> ScriptResult<i64> {
+            let (address, read_only) = to_address(address)?
+                .resolve(DataType::Dword, false)
+                .map_err(|e| e.to_string())?;
+            ctx.block_on(plc::read_dword(client_id, address, read_only))
+                .map(i64::from)
+        },
+    );
+    let ctx = context.clone();
+    engine.register_fn(
+        "read_float",
+        move |client_id: i64, address: Dynamic| -> ScriptResult<f64> {
+            let (address, read_only) = to_address(address)?
+                .resolve(DataType::Float, false)
+                .map_err(|e| e.to_string())?;
+            ctx.block_on(plc::read_float(client_id, address, read_only))
+                .map(f64::from)
+        },
+    );
+
+    for (name, data_type) in [
+        ("write_bool", DataType::Bool),
+        ("write_word", DataType::Word),
+        ("write_dword", DataType::Dword),
+        ("write_float", DataType::Float),
+    ] {
+        let ctx = context.clone();
+        engine.register_fn(
+            name,
+            move |client_id: i64, address: Dynamic, value: Dynamic| -> ScriptResult<()> {
+                ctx.write(client_id, address, data_type, to_number(value)?)
+            },
+        );
+    }
+
+    let ctx = context.clone();
+    engine.register_fn("read_tag", move |name: &str| -> ScriptResult<f64> {
+        ctx.block_on(tags::read_tag(name))
+    });
+    let ctx = context.clone();
+    engine.register_fn(
+        "write_tag",
+        move |name: &str, value: Dynamic| -> ScriptResult<()> {
+            let value = to_number(value)?;
+            let tag = ctx.block_on(tags::get_tag(name))?;
+            let raw = tag.to_raw(value).map_err(|e| e.to_string())?;
+            ctx.block_on(guard::check(tag.client_id, tag.address, tag.data_type, raw))?;
+            ctx.block_on(tags::write_tag(&tag, raw, None))?;
+            ctx.handle
+                .block_on(guard::commit(tag.client_id, tag.address, tag.data_type));
+            Ok(())
+        },
+    );
+
+    engine
+}
+
+// 脚本结束后更新状态并推送，同时清理过多的历史记录
+async fn finish(id: &str, state: ScriptState, error: Option<String>) {
+    let info = {
+        let mut scripts = SCRIPTS.lock().await;
+        let Some(script) = scripts.get_mut(id) else {
+            return;
+        };
+        script.info.state = state;
+        script.info.finished = Some(unix_millis());
+        script.info.error = error;
+        let info = script.info.clone();
+
+        let mut finished: Vec<(u64, String)> = scripts
+            .values()
+            .filter(|script| script.info.state != ScriptState::Running)
+            .map(|script| (script.info.started, script.info.id.clone()))
+            .collect();
+        if finished.len() > MAX_FINISHED_SCRIPTS {
+            finished.sort();
+            let excess = finished.len() - MAX_FINISHED_SCRIPTS;
+            for (_, id) in finished.into_iter().take(excess) {
+                scripts.remove(&id);
+            }
+        }
+        info
+    };
+    notify_script_state(info);
+}
+
+/// 编译并在后台运行脚本，返回脚本ID
+pub async fn run(name: String, source: String) -> Result<String> {
+    let id = format!("script-{}", NEXT_SCRIPT_ID.fetch_add(1, Ordering::Relaxed));
+    let cancel = Arc::new(AtomicBool::new(false));
+    let context = ScriptContext {
+        id: id.clone(),
+        handle: Handle::current(),
+        cancel: cancel.clone(),
+    };
+
+    // 语法错误直接返回给调用方，AST 不能跨线程传递，运行时在脚本线程中重新编译
+    build_engine(&context)
+        .compile(&source)
+        .map_err(|e| PLCError::Other(format!("脚本语法错误: {}", e)))?;
+
+    let info = ScriptInfo {
+        id: id.clone(),
+        name,
+        state: ScriptState::Running,
+        started: unix_millis(),
+        finished: None,
+        error: None,
+    };
+    SCRIPTS.lock().await.insert(
+        id.clone(),
+        Script {
+            info: info.clone(),
+            cancel,
+        },
+    );
+    notify_script_state(info);
+
+    tokio::spawn(async move {
+        let script_id = context.id.clone();
+        let result =
+            tokio::task::spawn_blocking(move || match build_engine(&context).run(&source) {
+                Ok(()) => (ScriptState::Completed, None),
+                // 停止时的错误可能被脚本函数调用包装，以停止标志判断
+                Err(_) if context.cancel.load(Ordering::Relaxed) => (ScriptState::Stopped, None),
+                Err(e) => (ScriptState::Failed, Some(e.to_string())),
+            })
+            .await;
+
+        let (state, error) =
+            result.unwrap_or_else(|e| (ScriptState::Failed, Some(format!("脚本线程异常: {}", e))));
+        if let Some(error) = &error {
+            output(&script_id, ScriptOutputKind::Error, error.clone());
+        }
+        finish(&script_id, state, error).await;
+    });
+
+    Ok(id)
+}
+
+/// 请求停止脚本，正在进行的读写完成后脚本才会结束
+pub async fn stop(id: &str) -> Result<()> {
+    let scripts = SCRIPTS.lock().await;
+    let script = scripts
+        .get(id)
+        .ok_or_else(|| PLCError::Other(format!("脚本未找到: {}", id)))?;
+    if script.info.state != ScriptState::Running {
+        return Err(PLCError::Other(format!("脚本 {} 未在运行", id)));
+    }
+    script.cancel.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+pub async fn list() -> Vec<ScriptInfo> {
+    let mut scripts: Vec<ScriptInfo> = SCRIPTS
+        .lock()
+        .await
+        .values()
+        .map(|script| script.info.clone())
+        .collect();
+    scripts.sort_by_key(|script| script.started);
+    scripts
+}
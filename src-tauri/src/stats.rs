@@ -0,0 +1,128 @@
+use serde::Serialize;
+use std::time::Instant;
+
+// 实际周期超过设定周期的倍数即视为超时
+const OVERRUN_FACTOR: f64 = 1.5;
+
+/// 累计的最小、最大和平均值
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Metric {
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    #[serde(skip)]
+    count: u64,
+}
+
+impl Metric {
+    pub fn add(&mut self, value: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        self.avg += (value - self.avg) / self.count as f64;
+    }
+}
+
+/// 单个任务的计时统计
+#[derive(Debug, Default, Clone)]
+pub struct TaskTiming {
+    last_start: Option<Instant>,
+    pub period_ms: Metric,
+    pub jitter_ms: Metric,
+    pub execution_ms: Metric,
+    pub overruns: u64,
+    pub successes: u64,
+    pub failures: u64,
+}
+
+impl TaskTiming {
+    // 记录一次执行，返回本次是否超时
+    pub fn record(
+        &mut self,
+        started: Instant,
+        interval_ms: u64,
+        execution_ms: f64,
+        success: bool,
+    ) -> bool {
+        let mut overrun = false;
+        if let Some(last_start) = self.last_start.replace(started) {
+            let period = started.duration_since(last_start).as_secs_f64() * 1000.0;
+            self.period_ms.add(period);
+            self.jitter_ms.add((period - interval_ms as f64).abs());
+            if period > interval_ms as f64 * OVERRUN_FACTOR {
+                self.overruns += 1;
+                overrun = true;
+            }
+        }
+
+        self.execution_ms.add(execution_ms);
+        if success {
+            self.successes += 1;
+        } else {
+            self.failures += 1;
+        }
+        overrun
+    }
+}
+
+/// 单个连接的请求统计
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionTiming {
+    pub requests: u64,
+    pub successes: u64,
+    pub failures: u64,
+    pub execution_ms: Metric,
+    // 该连接下所有任务的超时次数之和
+    pub overruns: u64,
+}
+
+impl ConnectionTiming {
+    pub fn record(&mut self, execution_ms: f64, success: bool, overruns: u64) {
+        self.requests += 1;
+        self.execution_ms.add(execution_ms);
+        if success {
+            self.successes += 1;
+        } else {
+            self.failures += 1;
+        }
+        self.overruns += overruns;
+    }
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStats {
+    pub client_id: i64,
+    pub address: u16,
+    pub data_type: u8,
+    pub read_only: bool,
+    pub interval_ms: u64,
+    pub period_ms: Metric,
+    pub jitter_ms: Metric,
+    pub execution_ms: Metric,
+    pub overruns: u64,
+    pub successes: u64,
+    pub failures: u64,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionStats {
+    pub client_id: i64,
+    #[serde(flatten)]
+    pub timing: ConnectionTiming,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulerStats {
+    pub tasks: Vec<TaskStats>,
+    pub connections: Vec<ConnectionStats>,
+}
@@ -0,0 +1,779 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tokio::sync::Mutex;
+
+use crate::alarm;
+use crate::expr::Expr;
+use crate::logger;
+use crate::notice::{notify_tag_value, Quality, TagValue};
+use crate::plc::{
+    generate_task_key, read_bool, read_dword, read_float, read_word, unix_millis, write_bool,
+    write_dword, write_float, write_word, AddressRef, DataType, PLCError, PlcValue, RegisterTable,
+    TaskDefinition, TaskKey, WriteVerify, TASK_SCHEDULER,
+};
+
+type Result<T> = std::result::Result<T, PLCError>;
+
+// 双字和浮点数的字序
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordOrder {
+    LowFirst = 1,
+    HighFirst = 2,
+}
+
+impl From<u8> for WordOrder {
+    fn from(value: u8) -> Self {
+        match value {
+            2 => WordOrder::HighFirst,
+            _ => WordOrder::LowFirst, // 默认低字在前，与寄存器读写一致
+        }
+    }
+}
+
+impl WordOrder {
+    // 在低字在前的解码结果上调整字序，交换两次即还原
+    fn apply(&self, value: u32) -> u32 {
+        match self {
+            WordOrder::LowFirst => value,
+            WordOrder::HighFirst => value.rotate_left(16),
+        }
+    }
+}
+
+fn default_word_order() -> u8 {
+    WordOrder::LowFirst as u8
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+/// 标签定义，工程值 = 原始值 × scale + offset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub client_id: String,
+    // 原始偏移或 40001、%MW100 形式的地址引用，引用的数据区决定 read_only
+    pub address: AddressRef,
+    pub data_type: u8,
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default = "default_word_order")]
+    pub word_order: u8,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    #[serde(default)]
+    pub offset: f64,
+    // 工程单位，仅用于显示
+    #[serde(default)]
+    pub unit: String,
+    // 为零时只定义标签，不注册轮询任务
+    #[serde(default)]
+    pub interval_ms: u64,
+}
+
+/// 解析后的标签
+#[derive(Debug, Clone)]
+pub struct Tag {
+    pub definition: TagDefinition,
+    pub client_id: i64,
+    pub address: u16,
+    pub data_type: DataType,
+    pub word_order: WordOrder,
+}
+
+impl Tag {
+    fn parse(mut definition: TagDefinition) -> Result<Tag> {
+        if definition.name.trim().is_empty() {
+            return Err(PLCError::Other("标签名不能为空".to_string()));
+        }
+        if definition.scale == 0.0 {
+            return Err(PLCError::Other("缩放系数不能为零".to_string()));
+        }
+        let client_id = definition
+            .client_id
+            .parse()
+            .map_err(|e| PLCError::Other(format!("无效的客户端ID: {}", e)))?;
+        let data_type = DataType::from(definition.data_type);
+
+        // 保存为原始偏移，导出和列表中不再出现地址引用
+        let (address, read_only) = definition
+            .address
+            .resolve(data_type, definition.read_only)?;
+        definition.address = AddressRef::Offset(address);
+        definition.read_only = read_only;
+
+        Ok(Tag {
+            client_id,
+            address,
+            data_type,
+            word_order: WordOrder::from(definition.word_order),
+            definition,
+        })
+    }
+
+    pub fn key(&self) -> TaskKey {
+        generate_task_key(
+            self.client_id,
+            self.address,
+            self.data_type,
+            self.definition.read_only,
+        )
+    }
+
+    fn polled(&self) -> bool {
+        self.definition.interval_ms > 0
+    }
+
+    // 布尔值不做缩放
+    fn scale(&self, raw: f64) -> f64 {
+        match self.data_type {
+            DataType::Bool => raw,
+            _ => raw * self.definition.scale + self.definition.offset,
+        }
+    }
+
+    // 把低字在前的原始值换算为工程值
+    pub fn engineering(&self, value: PlcValue) -> f64 {
+        let raw = match value {
+            PlcValue::Dword(value) => self.word_order.apply(value) as f64,
+            PlcValue::Float(value) => f32::from_bits(self.word_order.apply(value.to_bits())) as f64,
+            _ => value.as_f64(),
+        };
+        self.scale(raw)
+    }
+
+    /// 把工程值换算为写入的原始值
+    pub fn to_raw(&self, value: f64) -> Result<f64> {
+        // NaN 会通过范围检查并被转换为 0 写入
+        if !value.is_finite() {
+            return Err(PLCError::Other(format!(
+                "标签 {} 的写入值 {} 不是有效的数值",
+                self.definition.name, value
+            )));
+        }
+        if self.data_type == DataType::Bool {
+            return Ok((value != 0.0) as u8 as f64);
+        }
+
+        let raw = (value - self.definition.offset) / self.definition.scale;
+        if !raw.is_finite() {
+            return Err(PLCError::Other(format!(
+                "标签 {} 的写入值 {} 超出范围",
+                self.definition.name, value
+            )));
+        }
+        let max = match self.data_type {
+            DataType::Word => u16::MAX as f64,
+            DataType::Dword => u32::MAX as f64,
+            _ => return Ok(raw),
+        };
+        let raw = raw.round();
+        if raw < 0.0 || raw > max {
+            return Err(PLCError::Other(format!(
+                "标签 {} 的写入值 {} 超出范围",
+                self.definition.name, value
+            )));
+        }
+        Ok(raw)
+    }
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentValue {
+    pub timestamp: u64,
+    pub value: f64,
+}
+
+/// 虚拟标签定义，数值由表达式根据其他标签的最新值计算
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VirtualTagDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub expression: String,
+    #[serde(default)]
+    pub unit: String,
+}
+
+#[derive(Clone)]
+struct VirtualTag {
+    definition: VirtualTagDefinition,
+    expr: Expr,
+    inputs: Vec<String>,
+}
+
+#[derive(Default, Clone)]
+struct TagTable {
+    tags: HashMap<String, Tag>,
+    // 轮询任务到标签名的索引，用于按标签推送事件
+    by_task: HashMap<TaskKey, Vec<String>>,
+    virtual_tags: HashMap<String, VirtualTag>,
+    // 所有标签（包括虚拟标签）的最新工程值，读取失败的标签不在其中
+    latest: HashMap<String, f64>,
+}
+
+impl TagTable {
+    fn insert(&mut self, tag: Tag) -> Option<Tag> {
+        let name = tag.definition.name.clone();
+        let previous = self.remove(&name);
+        self.by_task
+            .entry(tag.key())
+            .or_default()
+            .push(name.clone());
+        self.tags.insert(name, tag);
+        previous
+    }
+
+    fn remove(&mut self, name: &str) -> Option<Tag> {
+        let tag = self.tags.remove(name)?;
+        self.latest.remove(name);
+        let key = tag.key();
+        if let Some(names) = self.by_task.get_mut(&key) {
+            names.retain(|other| other != name);
+            if names.is_empty() {
+                self.by_task.remove(&key);
+            }
+        }
+        Some(tag)
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.tags.contains_key(name) || self.virtual_tags.contains_key(name)
+    }
+
+    // 仍被虚拟标签引用的标签不能删除
+    fn check_unreferenced(&self, name: &str) -> Result<()> {
+        match self
+            .virtual_tags
+            .values()
+            .find(|tag| tag.inputs.iter().any(|input| input == name))
+        {
+            Some(dependent) => Err(PLCError::Other(format!(
+                "标签 {} 仍被虚拟标签 {} 引用",
+                name, dependent.definition.name
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    // 从 name 出发沿虚拟标签的输入能否回到 target
+    fn depends_on(&self, name: &str, target: &str) -> bool {
+        match self.virtual_tags.get(name) {
+            Some(tag) => tag
+                .inputs
+                .iter()
+                .any(|input| input == target || self.depends_on(input, target)),
+            None => false,
+        }
+    }
+
+    // 同一任务只能以一种数据类型和周期轮询，否则后注册的会覆盖之前的任务
+    // define_tag 在修改标签表之前的全部检查
+    fn validate(&self, tag: &Tag) -> Result<()> {
+        if self.virtual_tags.contains_key(&tag.definition.name) {
+            return Err(PLCError::Other(format!(
+                "已存在同名的虚拟标签: {}",
+                tag.definition.name
+            )));
+        }
+        self.check_task_conflict(tag)
+    }
+
+    fn check_task_conflict(&self, tag: &Tag) -> Result<()> {
+        if !tag.polled() {
+            return Ok(());
+        }
+        let Some(names) = self.by_task.get(&tag.key()) else {
+            return Ok(());
+        };
+        let conflict = names
+            .iter()
+            .filter(|name| **name != tag.definition.name)
+            .map(|name| &self.tags[name])
+            .find(|other| {
+                other.polled()
+                    && (other.data_type != tag.data_type
+                        || other.definition.interval_ms != tag.definition.interval_ms)
+            });
+        match conflict {
+            Some(other) => Err(PLCError::Other(format!(
+                "标签 {} 与 {} 轮询同一地址，但数据类型或周期不同",
+                tag.definition.name, other.definition.name
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    // 是否还有其他标签在轮询同一任务
+    fn polls(&self, key: &TaskKey) -> bool {
+        self.by_task
+            .get(key)
+            .map(|names| names.iter().any(|name| self.tags[name].polled()))
+            .unwrap_or(false)
+    }
+}
+
+lazy_static! {
+    static ref TAGS: Mutex<TagTable> = Mutex::new(TagTable::default());
+}
+
+// 旧标签的轮询任务不再被任何标签使用时注销
+async fn release_task(previous: Option<Tag>, table_polls: bool) {
+    if let Some(tag) = previous {
+        if tag.polled() && !table_polls {
+            let _ = TASK_SCHEDULER
+                .unregister_task(
+                    tag.client_id,
+                    tag.address,
+                    tag.data_type as u8,
+                    tag.definition.read_only,
+                )
+                .await;
+        }
+    }
+}
+
+/// 新增或替换标签，设定了周期时注册轮询任务
+pub async fn define_tag(definition: TagDefinition) -> Result<()> {
+    let tag = Tag::parse(definition)?;
+    // 所有检查在注册轮询任务之前完成，避免留下无主的任务
+    TAGS.lock().await.validate(&tag)?;
+    if tag.polled() {
+        TASK_SCHEDULER
+            .register_task(
+                tag.client_id,
+                tag.definition.interval_ms,
+                tag.address,
+                tag.data_type as u8,
+                tag.definition.read_only,
+            )
+            .await?;
+    }
+
+    // 先释放标签表再操作调度器
+    let (previous, still_polled) = {
+        let mut table = TAGS.lock().await;
+        let previous = table.insert(tag);
+        let still_polled = match &previous {
+            Some(previous) => table.polls(&previous.key()),
+            None => false,
+        };
+        (previous, still_polled)
+    };
+    release_task(previous, still_polled).await;
+    Ok(())
+}
+
+/// 连接移除后删除其标签及报警，依赖它们的虚拟标签变为无数值
+pub async fn remove_client_tags(client_id: i64) {
+    let names: Vec<String> = {
+        let mut table = TAGS.lock().await;
+        let names: Vec<String> = table
+            .tags
+            .values()
+            .filter(|tag| tag.client_id == client_id)
+            .map(|tag| tag.definition.name.clone())
+            .collect();
+        for name in &names {
+            table.remove(name);
+        }
+        names
+    };
+
+    let timestamp = unix_millis();
+    let error = "连接已移除".to_string();
+    for name in names {
+        alarm::remove_for_tag(&name).await;
+        logger::update(&name, None).await;
+        propagate(
+            &name,
+            None,
+            Quality::BadComm,
+            Some(error.clone()),
+            timestamp,
+        )
+        .await;
+    }
+}
+
+/// 按顺序校验一组标签定义，检查与逐个调用 define_tag 相同，但不修改标签表
+pub async fn validate_tags(definitions: Vec<TagDefinition>) -> Vec<Result<()>> {
+    let mut table = TAGS.lock().await.clone();
+    definitions
+        .into_iter()
+        .map(|definition| {
+            let tag = Tag::parse(definition)?;
+            table.validate(&tag)?;
+            table.insert(tag);
+            Ok(())
+        })
+        .collect()
+}
+
+pub async fn remove_tag(name: &str) -> Result<()> {
+    let (previous, still_polled) = {
+        let mut table = TAGS.lock().await;
+        table.check_unreferenced(name)?;
+        let previous = table
+            .remove(name)
+            .ok_or_else(|| PLCError::TagNotFound(name.to_string()))?;
+        let still_polled = table.polls(&previous.key());
+        (Some(previous), still_polled)
+    };
+    release_task(previous, still_polled).await;
+    alarm::remove_for_tag(name).await;
+    Ok(())
+}
+
+pub async fn list_tags() -> Vec<TagDefinition> {
+    let mut definitions: Vec<TagDefinition> = TAGS
+        .lock()
+        .await
+        .tags
+        .values()
+        .map(|tag| tag.definition.clone())
+        .collect();
+    definitions.sort_by(|a, b| a.name.cmp(&b.name));
+    definitions
+}
+
+/// 普通标签或虚拟标签是否存在
+pub async fn tag_exists(name: &str) -> bool {
+    TAGS.lock().await.contains(name)
+}
+
+/// 各标签最近一次的工程值，尚无有效值的标签为 None
+pub async fn latest_values(names: &[String]) -> HashMap<String, Option<f64>> {
+    let table = TAGS.lock().await;
+    names
+        .iter()
+        .map(|name| (name.clone(), table.latest.get(name).copied()))
+        .collect()
+}
+
+pub async fn get_tag(name: &str) -> Result<Tag> {
+    TAGS.lock()
+        .await
+        .tags
+        .get(name)
+        .cloned()
+        .ok_or_else(|| PLCError::TagNotFound(name.to_string()))
+}
+
+/// 读取标签的工程值
+pub async fn read_tag(name: &str) -> Result<f64> {
+    // 虚拟标签没有对应的地址，返回最近一次的计算结果
+    {
+        let table = TAGS.lock().await;
+        if table.virtual_tags.contains_key(name) {
+            return table
+                .latest
+                .get(name)
+                .copied()
+                .ok_or_else(|| PLCError::Other(format!("虚拟标签 {} 暂无数值", name)));
+        }
+    }
+
+    let tag = get_tag(name).await?;
+    let (client_id, address, read_only) = (tag.client_id, tag.address, tag.definition.read_only);
+    let value = match tag.data_type {
+        DataType::Bool => PlcValue::Bool(read_bool(client_id, address).await?),
+        DataType::Word => PlcValue::Word(read_word(client_id, address, read_only).await?),
+        DataType::Dword => PlcValue::Dword(read_dword(client_id, address, read_only).await?),
+        DataType::Float => PlcValue::Float(read_float(client_id, address, read_only).await?),
+    };
+    Ok(tag.engineering(value))
+}
+
+/// 写入已换算好的原始值
+pub async fn write_tag(tag: &Tag, raw: f64, verify: Option<WriteVerify>) -> Result<()> {
+    if tag.definition.read_only {
+        return Err(PLCError::Other(format!(
+            "标签 {} 为只读",
+            tag.definition.name
+        )));
+    }
+
+    let (client_id, address) = (tag.client_id, tag.address);
+    match tag.data_type {
+        DataType::Bool => write_bool(client_id, address, raw != 0.0, verify).await,
+        DataType::Word => write_word(client_id, address, raw as u16, verify).await,
+        DataType::Dword => {
+            let value = tag.word_order.apply(raw as u32);
+            write_dword(client_id, address, value, verify).await
+        }
+        DataType::Float => {
+            let bits = tag.word_order.apply((raw as f32).to_bits());
+            write_float(client_id, address, f32::from_bits(bits), verify).await
+        }
+    }
+}
+
+// 找出与任务对应的标签，同一地址的不同类型标签互不影响
+async fn tags_for(task: &TaskDefinition) -> Vec<Tag> {
+    let table = TAGS.lock().await;
+    match table.by_task.get(&task.key()) {
+        Some(names) => names
+            .iter()
+            .map(|name| &table.tags[name])
+            .filter(|tag| tag.data_type == task.data_type)
+            .cloned()
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// 与任务对应的第一个标签定义
+pub async fn find_for_task(task: &TaskDefinition) -> Option<TagDefinition> {
+    tags_for(task)
+        .await
+        .into_iter()
+        .next()
+        .map(|tag| tag.definition)
+}
+
+/// 标签最近的工程值，用于趋势图打开时回填
+pub async fn recent_values(name: &str, since: u64) -> Result<Vec<RecentValue>> {
+    let tag = get_tag(name).await?;
+    Ok(TASK_SCHEDULER
+        .recent_values(&tag.key(), since)
+        .await
+        .into_iter()
+        .map(|(timestamp, value)| RecentValue {
+            timestamp,
+            value: tag.engineering(value),
+        })
+        .collect())
+}
+
+/// 按任务的第一个标签换算的工程值，没有标签时为 None
+pub async fn engineering_value(task: &TaskDefinition, value: PlcValue) -> Option<f64> {
+    tags_for(task)
+        .await
+        .first()
+        .map(|tag| tag.engineering(value))
+}
+
+/// 推送任务的新值到其标签
+pub async fn publish_value(task: &TaskDefinition, value: PlcValue, timestamp: u64) {
+    for tag in tags_for(task).await {
+        let value = tag.engineering(value);
+        logger::update(&tag.definition.name, Some(value)).await;
+        alarm::evaluate(&tag.definition.name, value).await;
+        notify_tag_value(
+            TagValue {
+                name: tag.definition.name.clone(),
+                client_id: tag.client_id,
+                address: tag.address,
+                value: Some(value),
+                quality: Quality::Good,
+                error: None,
+                timestamp,
+            },
+            Some(RegisterTable::of(tag.data_type, tag.definition.read_only)),
+        );
+        propagate(
+            &tag.definition.name,
+            Some(value),
+            Quality::Good,
+            None,
+            timestamp,
+        )
+        .await;
+    }
+}
+
+pub async fn publish_error(task: &TaskDefinition, quality: Quality, error: &str, timestamp: u64) {
+    for tag in tags_for(task).await {
+        logger::update(&tag.definition.name, None).await;
+        notify_tag_value(
+            TagValue {
+                name: tag.definition.name.clone(),
+                client_id: tag.client_id,
+                address: tag.address,
+                value: None,
+                quality,
+                error: Some(error.to_string()),
+                timestamp,
+            },
+            Some(RegisterTable::of(tag.data_type, tag.definition.read_only)),
+        );
+        let error = Some(error.to_string());
+        propagate(&tag.definition.name, None, quality, error, timestamp).await;
+    }
+}
+
+/// 新增或替换虚拟标签，输入必须是已存在的标签且不能形成循环引用
+pub async fn define_virtual_tag(definition: VirtualTagDefinition) -> Result<()> {
+    let name = definition.name.trim().to_string();
+    if name.is_empty() {
+        return Err(PLCError::Other("标签名不能为空".to_string()));
+    }
+    let expr = Expr::parse(&definition.expression)
+        .map_err(|e| PLCError::Other(format!("表达式错误: {}", e)))?;
+    let inputs = expr.variables();
+
+    let update = {
+        let mut table = TAGS.lock().await;
+        if table.tags.contains_key(&name) {
+            return Err(PLCError::Other(format!("已存在同名的标签: {}", name)));
+        }
+        for input in &inputs {
+            if !table.contains(input) {
+                return Err(PLCError::TagNotFound(input.clone()));
+            }
+            if *input == name || table.depends_on(input, &name) {
+                return Err(PLCError::Other(format!("虚拟标签 {} 存在循环引用", name)));
+            }
+        }
+
+        // 输入都已有数值时立即计算一次
+        let value = expr.eval(&|input| table.latest.get(input).copied()).ok();
+        match value {
+            Some(value) => table.latest.insert(name.clone(), value),
+            None => table.latest.remove(&name),
+        };
+        table.virtual_tags.insert(
+            name.clone(),
+            VirtualTag {
+                definition: VirtualTagDefinition {
+                    name: name.clone(),
+                    ..definition
+                },
+                expr,
+                inputs,
+            },
+        );
+        value
+    };
+
+    if let Some(value) = update {
+        let timestamp = unix_millis();
+        emit_virtual(&name, Some(value), Quality::Good, None, timestamp).await;
+        propagate(&name, Some(value), Quality::Good, None, timestamp).await;
+    }
+    Ok(())
+}
+
+pub async fn remove_virtual_tag(name: &str) -> Result<()> {
+    let mut table = TAGS.lock().await;
+    if !table.virtual_tags.contains_key(name) {
+        return Err(PLCError::TagNotFound(name.to_string()));
+    }
+    table.check_unreferenced(name)?;
+    table.virtual_tags.remove(name);
+    table.latest.remove(name);
+    drop(table);
+
+    alarm::remove_for_tag(name).await;
+    Ok(())
+}
+
+pub async fn list_virtual_tags() -> Vec<VirtualTagDefinition> {
+    let mut definitions: Vec<VirtualTagDefinition> = TAGS
+        .lock()
+        .await
+        .virtual_tags
+        .values()
+        .map(|tag| tag.definition.clone())
+        .collect();
+    definitions.sort_by(|a, b| a.name.cmp(&b.name));
+    definitions
+}
+
+// 虚拟标签的更新与普通标签一样推送、记录并参与报警
+async fn emit_virtual(
+    name: &str,
+    value: Option<f64>,
+    quality: Quality,
+    error: Option<String>,
+    timestamp: u64,
+) {
+    logger::update(name, value).await;
+    if let Some(value) = value {
+        alarm::evaluate(name, value).await;
+    }
+    notify_tag_value(
+        TagValue {
+            name: name.to_string(),
+            client_id: 0,
+            address: 0,
+            value,
+            quality,
+            error,
+            timestamp,
+        },
+        // 虚拟标签没有地址，推送给所有窗口
+        None,
+    );
+}
+
+// 标签数值变化后重新计算依赖它的虚拟标签，逐级向下传递
+async fn propagate(
+    name: &str,
+    value: Option<f64>,
+    quality: Quality,
+    error: Option<String>,
+    timestamp: u64,
+) {
+    let updates = {
+        let mut table = TAGS.lock().await;
+        match value {
+            Some(value) => table.latest.insert(name.to_string(), value),
+            None => table.latest.remove(name),
+        };
+
+        let mut updates = Vec::new();
+        let mut pending = vec![name.to_string()];
+        let mut visited = HashSet::new();
+        while let Some(changed) = pending.pop() {
+            let dependents: Vec<String> = table
+                .virtual_tags
+                .values()
+                .filter(|tag| tag.inputs.contains(&changed))
+                .map(|tag| tag.definition.name.clone())
+                .filter(|dependent| !visited.contains(dependent))
+                .collect();
+
+            for dependent in dependents {
+                visited.insert(dependent.clone());
+                let tag = &table.virtual_tags[&dependent];
+                let complete = tag
+                    .inputs
+                    .iter()
+                    .all(|input| table.latest.contains_key(input));
+                let update = if complete {
+                    match tag.expr.eval(&|input| table.latest.get(input).copied()) {
+                        Ok(value) => (Some(value), Quality::Good, None),
+                        Err(e) => (None, Quality::BadExpression, Some(e)),
+                    }
+                } else if value.is_none() {
+                    // 输入读取失败，沿用其质量
+                    (None, quality, error.clone())
+                } else {
+                    // 其他输入尚无数值，等待它们更新
+                    continue;
+                };
+
+                match update.0 {
+                    Some(value) => table.latest.insert(dependent.clone(), value),
+                    None => table.latest.remove(&dependent),
+                };
+                pending.push(dependent.clone());
+                updates.push((dependent, update));
+            }
+        }
+        updates
+    };
+
+    for (name, (value, quality, error)) in updates {
+        emit_virtual(&name, value, quality, error, timestamp).await;
+    }
+}
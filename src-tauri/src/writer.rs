@@ -0,0 +1,248 @@
+use serde::{Deserialize, Serialize};
+
+use crate::modbus::MODBUS_MANAGER;
+use crate::plc::{encode_dword, AddressRef, DataType, PLCError};
+
+// Modbus 协议单次写入的数量上限
+const MAX_WRITE_REGISTERS: u16 = 123;
+const MAX_WRITE_COILS: u16 = 1968;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+pub enum WriteValue {
+    Bool(bool),
+    Number(f64),
+}
+
+impl WriteValue {
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            WriteValue::Bool(value) => *value as u8 as f64,
+            WriteValue::Number(value) => *value,
+        }
+    }
+}
+
+/// 批量写入中的一项
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteItem {
+    pub address: AddressRef,
+    pub data_type: u8,
+    pub value: WriteValue,
+}
+
+/// 地址已解析为偏移的写入项
+#[derive(Debug, Clone)]
+pub struct ResolvedItem {
+    pub address: u16,
+    pub data_type: DataType,
+    pub value: WriteValue,
+}
+
+impl WriteItem {
+    pub fn resolve(&self) -> Result<ResolvedItem, PLCError> {
+        let data_type = DataType::from(self.data_type);
+        Ok(ResolvedItem {
+            address: self.address.resolve_writable(data_type)?,
+            data_type,
+            value: self.value,
+        })
+    }
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteResult {
+    pub index: usize,
+    pub address: u16,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+enum WriteData {
+    Coils(Vec<bool>),
+    Registers(Vec<u16>),
+}
+
+impl WriteData {
+    fn len(&self) -> u16 {
+        match self {
+            WriteData::Coils(values) => values.len() as u16,
+            WriteData::Registers(values) => values.len() as u16,
+        }
+    }
+
+    // 同类数据追加到末尾，超出协议上限时返回 false
+    fn try_append(&mut self, other: &WriteData) -> bool {
+        match (self, other) {
+            (WriteData::Coils(values), WriteData::Coils(more))
+                if values.len() + more.len() <= MAX_WRITE_COILS as usize =>
+            {
+                values.extend_from_slice(more);
+                true
+            }
+            (WriteData::Registers(values), WriteData::Registers(more))
+                if values.len() + more.len() <= MAX_WRITE_REGISTERS as usize =>
+            {
+                values.extend_from_slice(more);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// 一次写入请求，覆盖若干个连续地址的写入项
+struct WriteBlock {
+    address: u16,
+    data: WriteData,
+    // 所含写入项在原列表中的序号
+    items: Vec<usize>,
+}
+
+impl WriteBlock {
+    fn end(&self) -> u32 {
+        self.address as u32 + self.data.len() as u32
+    }
+}
+
+// 把写入值转换为线圈或寄存器数据，越界时返回错误；布尔值对任何类型都按 1 和 0 处理
+fn encode(item: &ResolvedItem) -> Result<WriteData, String> {
+    let number = item.value.as_f64();
+
+    let integer = |max: f64| {
+        if number.fract() != 0.0 || number < 0.0 || number > max {
+            Err(format!(
+                "地址 {} 的写入值 {} 超出范围",
+                item.address, number
+            ))
+        } else {
+            Ok(number)
+        }
+    };
+
+    match item.data_type {
+        DataType::Bool => Ok(WriteData::Coils(vec![number != 0.0])),
+        DataType::Word => Ok(WriteData::Registers(vec![integer(u16::MAX as f64)? as u16])),
+        DataType::Dword => Ok(WriteData::Registers(
+            encode_dword(integer(u32::MAX as f64)? as u32).to_vec(),
+        )),
+        DataType::Float => {
+            let value = number as f32;
+            if value.is_infinite() && number.is_finite() {
+                return Err(format!(
+                    "地址 {} 的写入值 {} 超出浮点数范围",
+                    item.address, number
+                ));
+            }
+            Ok(WriteData::Registers(encode_dword(value.to_bits()).to_vec()))
+        }
+    }
+}
+
+// 按原列表顺序分块，只合并列表中相邻且地址首尾相接的同类写入项
+fn build_blocks(items: &[ResolvedItem]) -> Result<Vec<WriteBlock>, String> {
+    let mut blocks: Vec<WriteBlock> = Vec::new();
+    for (index, item) in items.iter().enumerate() {
+        let data = encode(item)?;
+        if let Some(block) = blocks.last_mut() {
+            if block.end() == item.address as u32 && block.data.try_append(&data) {
+                block.items.push(index);
+                continue;
+            }
+        }
+        blocks.push(WriteBlock {
+            address: item.address,
+            data,
+            items: vec![index],
+        });
+    }
+    Ok(blocks)
+}
+
+/// 按列表顺序执行批量写入，某个请求失败后不再执行后续请求，结果按原列表顺序返回
+pub async fn write_batch(
+    client_id: i64,
+    items: &[ResolvedItem],
+) -> Result<Vec<WriteResult>, PLCError> {
+    // 写入前先校验全部数据，避免只写入一部分
+    let blocks = build_blocks(items).map_err(PLCError::Other)?;
+
+    let mut results = Vec::with_capacity(items.len());
+    let mut failure: Option<String> = None;
+    for block in blocks {
+        let error = match &failure {
+            Some(_) => Some("前序写入失败，未执行".to_string()),
+            None => {
+                let result = match &block.data {
+                    WriteData::Coils(values) => {
+                        MODBUS_MANAGER
+                            .write_multiple_coils(client_id, block.address, values)
+                            .await
+                    }
+                    WriteData::Registers(values) => {
+                        MODBUS_MANAGER
+                            .write_multiple_registers(client_id, block.address, values)
+                            .await
+                    }
+                };
+                result.err().map(|e| e.to_string())
+            }
+        };
+        if failure.is_none() {
+            failure = error.clone();
+        }
+
+        for index in block.items {
+            results.push(WriteResult {
+                index,
+                address: items[index].address,
+                success: error.is_none(),
+                error: error.clone(),
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(address: u16, data_type: DataType, value: WriteValue) -> ResolvedItem {
+        ResolvedItem {
+            address,
+            data_type,
+            value,
+        }
+    }
+
+    #[test]
+    fn coil_placed_last_is_written_last() {
+        let items = [
+            item(100, DataType::Word, WriteValue::Number(1.0)),
+            item(101, DataType::Word, WriteValue::Number(2.0)),
+            item(0, DataType::Bool, WriteValue::Bool(true)),
+        ];
+        let blocks = build_blocks(&items).unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].address, 100);
+        assert_eq!(blocks[0].items, vec![0, 1]);
+        assert!(matches!(blocks[1].data, WriteData::Coils(_)));
+        assert_eq!(blocks[1].items, vec![2]);
+    }
+
+    #[test]
+    fn only_adjacent_items_are_merged() {
+        let items = [
+            item(10, DataType::Word, WriteValue::Number(1.0)),
+            item(20, DataType::Word, WriteValue::Number(2.0)),
+            item(11, DataType::Word, WriteValue::Number(3.0)),
+        ];
+        let blocks = build_blocks(&items).unwrap();
+        let order: Vec<Vec<usize>> = blocks.iter().map(|block| block.items.clone()).collect();
+        assert_eq!(order, vec![vec![0], vec![1], vec![2]]);
+    }
+}